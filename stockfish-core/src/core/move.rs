@@ -5,11 +5,16 @@ use crate::prelude::*;
 /// Users of this struct must be aware of and observe the following rules and
 /// behavior:
 ///
-/// * a move *must not* go from an origin to the same destination
+/// * a move *must not* go from an origin to the same destination, since that
+///   encoding is reserved for drops
 /// * castling is encoded as a move from the king's starting square to the
 ///   rook's starting square
 /// * the [`Move::promotion`] must not be called and for moves whose type is
 ///   not [`MoveType::Promotion`]
+/// * drops are encoded as a move from the destination square to itself, with
+///   the dropped token in place of the promotion and move type bits
+/// * promotions to a king, which are only possible in antichess, are encoded
+///   as a normal move with every promotion bit set
 #[derive(Copy, Debug, Eq, PartialEq)]
 #[derive_const(Clone)]
 #[must_use]
@@ -19,8 +24,10 @@ enumeration! {
     /// Encodes the type of [`Move`] being made.
     ///
     /// Note that a [`MoveType::EnPassant`] represents the *capture* of a pawn
-    /// en passant, not a two square pawn movement.
-    pub MoveType, [ Normal, Promotion, EnPassant, Castling ]
+    /// en passant, not a two square pawn movement. A [`MoveType::Drop`] places
+    /// a previously-captured piece onto the board, which is only possible in
+    /// crazyhouse.
+    pub MoveType, [ Normal, Promotion, EnPassant, Castling, Drop ]
 }
 
 enumeration! {
@@ -46,6 +53,14 @@ impl Move {
     // * bit  6-11: origin square (from 0 to 63)
     // * bit 12-13: promotion token type - 2 (from KNIGHT-2 to QUEEN-2)
     // * bit 14-15: special move flag: promotion (1), en passant (2), castling (3)
+    //
+    // A drop has no origin square, so the origin is set to the destination
+    // (which no other move can do) and bits 12-15 instead hold the token
+    // being dropped.
+    //
+    // A promotion to a king doesn't fit in the promotion bits, so it's
+    // instead encoded as a normal move with both promotion bits set, which no
+    // other move may have.
     const DESTINATION_BITS: u8 = 6;
     const ORIGIN_BITS:      u8 = 6;
     const PROMOTION_BITS:   u8 = 2;
@@ -56,10 +71,16 @@ impl Move {
     const PROMOTION_SHIFT:   u8 = Self::ORIGIN_SHIFT      + Self::ORIGIN_BITS;
     const MOVE_TYPE_SHIFT:   u8 = Self::PROMOTION_SHIFT   + Self::PROMOTION_BITS;
 
+    const DROP_BITS:  u8 = Self::PROMOTION_BITS + Self::MOVE_TYPE_BITS;
+    const DROP_SHIFT: u8 = Self::PROMOTION_SHIFT;
+
     const DESTINATION_MASK: u8 = (1 << Self::DESTINATION_BITS) - 1;
     const ORIGIN_MASK:      u8 = (1 << Self::ORIGIN_BITS) - 1;
     const PROMOTION_MASK:   u8 = (1 << Self::PROMOTION_BITS) - 1;
     const MOVE_TYPE_MASK:   u8 = (1 << Self::MOVE_TYPE_BITS) - 1;
+    const DROP_MASK:        u8 = (1 << Self::DROP_BITS) - 1;
+
+    const KING_PROMOTION: u8 = Self::PROMOTION_MASK;
 
     /// Encodes a normal move from an `origin` square to a `destination` square.
     #[inline]
@@ -128,6 +149,17 @@ impl Move {
         )
     }
 
+    /// Encodes a move of a pawn from an `origin` square to a `destination`
+    /// square that results in promotion to a king, as is allowed in
+    /// antichess.
+    #[inline]
+    pub const fn new_promote_king(origin: Square, destination: Square) -> Self {
+        Self(
+            Self::new(origin, destination).0
+                | ((Self::KING_PROMOTION as u16) << Self::PROMOTION_SHIFT)
+        )
+    }
+
     /// Encodes the capture of a pawn en passant by a token starting on
     /// `origin` and ending on the `pawn`'s square.
     #[inline]
@@ -149,7 +181,72 @@ impl Move {
         )
     }
 
+    /// Encodes dropping a `token` from the moving side's pocket onto the empty
+    /// `destination` square.
+    ///
+    /// Only pawns, knights, bishops, rooks, and queens may be dropped, and
+    /// pawns may not be dropped on the first or eighth ranks.
+    #[inline]
+    pub const fn new_drop(token: Token, destination: Square) -> Self {
+        debug_assert!(Self::is_droppable(token, destination));
+
+        let bits_destination: u16 = destination.as_u8().into();
+        let bits_token:       u16 = token      .as_u8().into();
+
+        Self(
+            (bits_destination << Self::DESTINATION_SHIFT) |
+            (bits_destination << Self::ORIGIN_SHIFT)      |
+            (bits_token       << Self::DROP_SHIFT)
+        )
+    }
+
+    /// Decodes a move from its raw 16-bit representation, as produced by
+    /// [`Move::as_u16`].
+    ///
+    /// Moves whose origin and destination are the same square are rejected
+    /// unless they're valid drops, as are moves other than promotions which
+    /// have promotion bits set, so that every move has exactly one encoding.
+    /// In particular, zero never decodes to a move. The resulting move is not
+    /// guaranteed to make any sense in a given position, so moves from
+    /// untrusted sources (transposition tables, opening books, and the like)
+    /// must be validated before being played.
+    #[inline]
+    #[must_use]
+    pub const fn from_u16(bits: u16) -> Option<Self> {
+        let mv = Self(bits);
+
+        let origin      = mv.extract(Self::ORIGIN_SHIFT,      Self::ORIGIN_MASK);
+        let destination = mv.extract(Self::DESTINATION_SHIFT, Self::DESTINATION_MASK);
+        let move_type   = mv.extract(Self::MOVE_TYPE_SHIFT,   Self::MOVE_TYPE_MASK);
+        let promotion   = mv.extract(Self::PROMOTION_SHIFT,   Self::PROMOTION_MASK);
+
+        if origin == destination {
+            let token = mv.extract(Self::DROP_SHIFT, Self::DROP_MASK);
+
+            return match (Token::from_u8(token), Square::from_u8(destination)) {
+                (Some(token), Some(square)) if Self::is_droppable(token, square) => Some(mv),
+                _                                                                 => None,
+            };
+        }
+
+        if move_type != MoveType::Promotion as u8 && promotion != 0 && !mv.is_king_promotion() {
+            return None;
+        }
+
+        Some(mv)
+    }
+
+    /// Returns the raw 16-bit representation of the move.
+    #[inline]
+    #[must_use]
+    pub const fn as_u16(self) -> u16 {
+        self.0
+    }
+
     /// Returns the square the moving token began on.
+    ///
+    /// Dropped tokens don't begin anywhere on the board, so for
+    /// [`MoveType::Drop`] this is the same as [`Move::destination`].
     #[inline]
     pub const fn origin(self) -> Square {
         let bits = self.extract(Self::ORIGIN_SHIFT, Self::ORIGIN_MASK);
@@ -179,6 +276,14 @@ impl Move {
     /// Returns the type of move encoded.
     #[inline]
     pub const fn move_type(self) -> MoveType {
+        if self.is_drop() {
+            return MoveType::Drop;
+        }
+
+        if self.is_king_promotion() {
+            return MoveType::Promotion;
+        }
+
         let bits = self.extract(Self::MOVE_TYPE_SHIFT, Self::MOVE_TYPE_MASK);
 
         unsafe_optimization!(
@@ -197,10 +302,14 @@ impl Move {
     pub const fn promotion(self) -> Token {
         debug_assert!(MoveType::Promotion == self.move_type());
 
-        // We exclude pawns and kings from the type of token that may be
-        // promoted to (so that this information fits in two bits), so we need
-        // to add the value of a knight in order to get the value of the token
-        // being promoted to.
+        if self.is_king_promotion() {
+            return Token::King;
+        }
+
+        // We exclude pawns and kings (which are encoded separately) from the
+        // type of token that may be promoted to (so that this information fits
+        // in two bits), so we need to add the value of a knight in order to
+        // get the value of the token being promoted to.
         //
         // This conversion could/should be implemented on the MovePromotion enum
         // itself, but given that it's *only* used here that's overkill.
@@ -213,6 +322,46 @@ impl Move {
         )
     }
 
+    /// Returns the type of token being dropped during this move.
+    ///
+    /// This method *must not* be called unless the move is a
+    /// [`MoveType::Drop`]. For all other move types, the result of this method
+    /// is undefined and must not be expected to produce reliable behavior.
+    #[inline]
+    pub const fn dropped(self) -> Token {
+        debug_assert!(self.is_drop());
+
+        let bits = self.extract(Self::DROP_SHIFT, Self::DROP_MASK);
+
+        unsafe_optimization!(
+            Token::from_u8(bits).unwrap(),
+            Token::from_u8_unchecked(bits),
+        )
+    }
+
+    #[inline]
+    const fn is_drop(self) -> bool {
+        self.extract(Self::ORIGIN_SHIFT,      Self::ORIGIN_MASK) ==
+        self.extract(Self::DESTINATION_SHIFT, Self::DESTINATION_MASK)
+    }
+
+    #[inline]
+    const fn is_king_promotion(self) -> bool {
+        !self.is_drop()
+            && self.extract(Self::MOVE_TYPE_SHIFT, Self::MOVE_TYPE_MASK) == MoveType::Normal as u8
+            && self.extract(Self::PROMOTION_SHIFT, Self::PROMOTION_MASK) == Self::KING_PROMOTION
+    }
+
+    /// Returns [`true`] if `token` may ever be dropped onto `square`.
+    #[inline]
+    const fn is_droppable(token: Token, square: Square) -> bool {
+        match token {
+            Token::King => false,
+            Token::Pawn => !matches!(square.rank(), Rank::_1 | Rank::_8),
+            _           => true,
+        }
+    }
+
     #[inline]
     const fn extract(self, shift: u8, mask: u8) -> u8 {
         #[allow(clippy::cast_possible_truncation)] {
@@ -242,6 +391,32 @@ mod tests {
         assert_eq!(MoveType::Normal, mv.move_type());
     }
 
+    #[test]
+    fn from_u16() {
+        for mv in [
+            Move::new(Square::C3, Square::F7),
+            Move::new_promote_rook(Square::A7, Square::B8),
+            Move::new_promote_king(Square::H2, Square::H1),
+            Move::new_en_passant(Square::E5, Square::D6),
+            Move::new_castling(Square::E1, Square::H1),
+            Move::new_drop(Token::Pawn,  Square::A2),
+            Move::new_drop(Token::Queen, Square::H8),
+        ] {
+            assert_eq!(Some(mv), Move::from_u16(mv.as_u16()));
+        }
+
+        // origin and destination are the same
+        assert_eq!(None, Move::from_u16(0));
+        assert_eq!(None, Move::from_u16(Move::new_castling(Square::B1, Square::C1).as_u16() ^ 0b11));
+
+        // promotion bits set on a move that isn't a promotion
+        assert_eq!(None, Move::from_u16(Move::new(Square::B1, Square::A3).as_u16() | (1 << 12)));
+
+        // drops of kings, and of pawns onto the back ranks
+        assert_eq!(None, Move::from_u16(Move::new_drop(Token::Queen, Square::E4).as_u16() + (1 << 12)));
+        assert_eq!(None, Move::from_u16(Move::new_drop(Token::Knight, Square::E8).as_u16() ^ (1 << 12)));
+    }
+
     #[test]
     fn new_promote_knight() {
         let mv = Move::new_promote_knight(Square::D6, Square::B1);
@@ -284,6 +459,20 @@ mod tests {
         assert_eq!(Token::Queen,        mv.promotion());
     }
 
+    #[test]
+    fn new_promote_king() {
+        let mv = Move::new_promote_king(Square::B2, Square::A1);
+
+        assert_eq!(Square::B2,          mv.origin());
+        assert_eq!(Square::A1,          mv.destination());
+        assert_eq!(MoveType::Promotion, mv.move_type());
+        assert_eq!(Token::King,         mv.promotion());
+
+        // a rook drop shares the same bits, but not an origin distinct from
+        // its destination
+        assert_eq!(MoveType::Drop, Move::new_drop(Token::Rook, Square::A1).move_type());
+    }
+
     #[test]
     fn new_en_passant() {
         let mv = Move::new_en_passant(Square::G7, Square::E1);
@@ -301,4 +490,17 @@ mod tests {
         assert_eq!(Square::H8,         mv.destination());
         assert_eq!(MoveType::Castling, mv.move_type());
     }
+
+    #[test]
+    fn new_drop() {
+        let mv = Move::new_drop(Token::Knight, Square::F3);
+
+        assert_eq!(Square::F3,     mv.origin());
+        assert_eq!(Square::F3,     mv.destination());
+        assert_eq!(MoveType::Drop, mv.move_type());
+        assert_eq!(Token::Knight,  mv.dropped());
+
+        assert_eq!(Token::Pawn, Move::new_drop(Token::Pawn, Square::A2).dropped());
+        assert_eq!(Token::Queen, Move::new_drop(Token::Queen, Square::H8).dropped());
+    }
 }
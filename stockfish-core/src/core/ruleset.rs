@@ -8,4 +8,58 @@ pub enum Ruleset {
 
     /// A game of Fischer random chess, where the starting state is randomized.
     Chess960,
+
+    /// A game of crazyhouse, where captured pieces switch sides and may later
+    /// be dropped back onto the board instead of making a move.
+    Crazyhouse,
+
+    /// A game of atomic chess, where every capture sets off an explosion that
+    /// destroys the capturing piece along with everything but pawns on the
+    /// surrounding squares. Blowing up the enemy king wins the game.
+    Atomic,
+
+    /// A game of antichess, where captures are compulsory and the king is an
+    /// ordinary piece. The first player to lose all of their pieces or to be
+    /// stalemated wins.
+    Antichess,
+
+    /// A game of three-check, where giving check for the third time wins.
+    ThreeCheck,
+
+    /// A game of king of the hill, where bringing the king to one of the four
+    /// central squares wins.
+    KingOfTheHill,
+
+    /// A game of horde, where a king-less white horde of pawns tries to
+    /// checkmate black, and black tries to capture every one of them.
+    Horde,
+
+    /// A game of racing kings, where neither side may give check and the
+    /// first king to reach the eighth rank wins. The game is drawn if black
+    /// reaches it on the move right after white does.
+    RacingKings,
+}
+
+impl Ruleset {
+    /// The number of checks a side must give to win a game of
+    /// [`Ruleset::ThreeCheck`], after which no more can be given.
+    pub const MAX_CHECKS: usize = 3;
+
+    /// Returns the FEN of the position a game under this ruleset normally
+    /// starts from. For [`Ruleset::Chess960`] this is the standard chess
+    /// starting position, which is one of its 960 possible starts.
+    #[inline]
+    #[must_use]
+    pub const fn start_fen(self) -> &'static str {
+        match self {
+            Self::Standard | Self::Chess960 | Self::Atomic | Self::KingOfTheHill =>
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+
+            Self::Crazyhouse  => "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR[] w KQkq - 0 1",
+            Self::Antichess   => "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w - - 0 1",
+            Self::ThreeCheck  => "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1 +0+0",
+            Self::Horde       => "rnbqkbnr/pppppppp/8/1PP2PP1/PPPPPPPP/PPPPPPPP/PPPPPPPP/PPPPPPPP w kq - 0 1",
+            Self::RacingKings => "8/8/8/8/8/8/krbnNBRK/qrbnNBRQ w - - 0 1",
+        }
+    }
 }
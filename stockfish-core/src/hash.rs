@@ -32,6 +32,13 @@ impl const From<u64> for Key {
     }
 }
 
+impl const From<Key> for u64 {
+    #[inline]
+    fn from(key: Key) -> Self {
+        key.0
+    }
+}
+
 impl const BitXor for Key {
     type Output = Self;
 
@@ -62,6 +69,11 @@ mod tests {
         assert_ne!("", format!("{:?}", Key::from(0)));
     }
 
+    #[test]
+    fn key_u64() {
+        assert_eq!(0xdead_beef_u64, u64::from(Key::from(0xdead_beef_u64)));
+    }
+
     #[test]
     fn key_bitxor() {
         let k1 = Key::from(0b1010_1111_u64);
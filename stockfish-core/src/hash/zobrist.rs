@@ -20,15 +20,24 @@ pub struct Zobrist {
     castling:   [Key; CastlingRights::COUNT],
     side:       Key,
     no_pawns:   Key,
+    pockets:    [[Key; Zobrist::POCKET_CAPACITY]; Piece::COUNT],
+    checks:     [[Key; Ruleset::MAX_CHECKS]; Color::COUNT],
 }
 
 impl Zobrist {
+    /// The most of any one piece that can be held in a pocket at once. Even
+    /// pawns are limited to sixteen, since promoted pieces revert to pawns
+    /// when captured.
+    pub const POCKET_CAPACITY: usize = 16;
+
     pub(crate) const fn new(seed: u64) -> Self {
         let mut prng = Prng::from(seed);
 
         let mut psq        = [[Key::default(); Square::COUNT]; Piece::COUNT];
         let mut en_passant = [Key::default(); File::COUNT];
         let mut castling   = [Key::default(); CastlingRights::COUNT];
+        let mut pockets    = [[Key::default(); Self::POCKET_CAPACITY]; Piece::COUNT];
+        let mut checks     = [[Key::default(); Ruleset::MAX_CHECKS]; Color::COUNT];
 
         let mut i;
         let mut j;
@@ -63,12 +72,45 @@ impl Zobrist {
             i += 1;
         }
 
+        let side     = prng.next_u64().into();
+        let no_pawns = prng.next_u64().into();
+
+        i = 0;
+
+        while i < Piece::COUNT {
+            j = 0;
+
+            while j < Self::POCKET_CAPACITY {
+                pockets[i][j] = prng.next_u64().into();
+
+                j += 1;
+            }
+
+            i += 1;
+        }
+
+        i = 0;
+
+        while i < Color::COUNT {
+            j = 0;
+
+            while j < Ruleset::MAX_CHECKS {
+                checks[i][j] = prng.next_u64().into();
+
+                j += 1;
+            }
+
+            i += 1;
+        }
+
         Self {
             psq,
             en_passant,
             castling,
-            side:     prng.next_u64().into(),
-            no_pawns: prng.next_u64().into(),
+            side,
+            no_pawns,
+            pockets,
+            checks,
         }
     }
 
@@ -78,6 +120,41 @@ impl Zobrist {
         self.psq[piece][square]
     }
 
+    /// Returns the Zobrist hash of there being at least `count + 1` of a given
+    /// piece on the board.
+    ///
+    /// XORing together the keys for every count from zero up to (but not
+    /// including) the number of each piece on the board gives a key that
+    /// uniquely identifies the material balance, regardless of where the
+    /// pieces are. This shares its keys with [`Zobrist::piece_square_key`],
+    /// which is harmless since the two are never mixed together.
+    #[inline]
+    pub const fn material_key(&self, piece: Piece, count: u8) -> Key {
+        self.psq[piece][count as usize]
+    }
+
+    /// Returns the Zobrist hash of there being at least `count + 1` of a given
+    /// piece in its owner's pocket, waiting to be dropped.
+    ///
+    /// As with [`Zobrist::material_key`], the keys for every count up to (but
+    /// not including) the number of each piece in hand are XORed together.
+    /// The `count` must be less than [`Zobrist::POCKET_CAPACITY`].
+    #[inline]
+    pub const fn pocket_key(&self, piece: Piece, count: u8) -> Key {
+        self.pockets[piece][count as usize]
+    }
+
+    /// Returns the Zobrist hash of a side having given at least `count + 1`
+    /// checks in three-check.
+    ///
+    /// As with [`Zobrist::material_key`], the keys for every count up to (but
+    /// not including) the number of checks given are XORed together. The
+    /// `count` must be less than [`Ruleset::MAX_CHECKS`].
+    #[inline]
+    pub const fn checks_key(&self, color: Color, count: u8) -> Key {
+        self.checks[color][count as usize]
+    }
+
     /// Returns the zobrist hash for a given en passant file.
     #[inline]
     pub const fn en_passant_key(&self, file: File) -> Key {
@@ -122,7 +199,9 @@ impl Zobrist {
         self.side
     }
 
-    /// TODO: document me!
+    /// Returns the key for a board with no pawns on it, which is used as the
+    /// starting point for pawn structure keys so that an empty pawn structure
+    /// doesn't hash to zero.
     #[inline]
     pub const fn no_pawns_key(&self) -> Key {
         self.no_pawns
@@ -166,5 +245,32 @@ mod tests {
 
         assert!(set.insert(zobrist.side_key()));
         assert!(set.insert(zobrist.no_pawns_key()));
+
+        for piece in Piece::iter() {
+            for count in 0..Zobrist::POCKET_CAPACITY {
+                assert!(set.insert(zobrist.pocket_key(piece, count.try_into().unwrap())));
+            }
+        }
+
+        for color in Color::iter() {
+            for count in 0..Ruleset::MAX_CHECKS {
+                assert!(set.insert(zobrist.checks_key(color, count.try_into().unwrap())));
+            }
+        }
+    }
+
+    #[test]
+    fn material_key() {
+        let zobrist = Zobrist::default();
+
+        assert_ne!(
+            zobrist.material_key(Piece::WhitePawn, 0),
+            zobrist.material_key(Piece::WhitePawn, 1),
+        );
+
+        assert_ne!(
+            zobrist.material_key(Piece::WhitePawn, 0),
+            zobrist.material_key(Piece::BlackPawn, 0),
+        );
     }
 }
@@ -0,0 +1,84 @@
+//! Counts the leaf nodes of the legal move tree from a position, broken down
+//! by each move from the root.
+//!
+//! Usage: `stockfish-perft <depth> [fen]`
+//!
+//! The FEN defaults to the standard starting position. FENs using Shredder-FEN
+//! castling letters are treated as Chess960.
+
+use stockfish_core::prelude::*;
+use stockfish_game::prelude::*;
+
+use std::process::ExitCode;
+use std::time::Instant;
+
+const START_POSITION: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+fn main() -> ExitCode {
+    let mut args = std::env::args().skip(1);
+
+    let Some(depth) = args.next().and_then(|depth| depth.parse::<u8>().ok()) else {
+        eprintln!("usage: stockfish-perft <depth> [fen]");
+        return ExitCode::FAILURE;
+    };
+
+    // FENs contain spaces, so accept them whether or not they were quoted
+    let fen = args.collect::<Vec<_>>().join(" ");
+    let fen = if fen.is_empty() { START_POSITION } else { &fen };
+
+    let mut position = match Position::try_from_fen_inferring_chess960(Ruleset::Standard, fen.as_bytes()) {
+        Ok(position) => position,
+        Err(error)   => {
+            eprintln!("invalid fen: {error}");
+            return ExitCode::FAILURE;
+        },
+    };
+
+    let start   = Instant::now();
+    let divided = position.divide(depth);
+    let nodes   = divided.iter().map(|&(_, nodes)| nodes).sum::<u64>();
+    let elapsed = start.elapsed();
+
+    for (mv, nodes) in divided {
+        println!("{}: {nodes}", uci(&position, mv));
+    }
+
+    println!();
+    println!("Nodes searched: {nodes}");
+    println!("Time: {}ms", elapsed.as_millis());
+
+    ExitCode::SUCCESS
+}
+
+/// Formats a move in UCI notation. Castling is written as the king moving to
+/// its destination in standard chess, but as the king capturing its own rook in
+/// Chess960.
+fn uci(position: &Position, mv: Move) -> String {
+    let     origin      = mv.origin();
+    let mut destination = mv.destination();
+
+    if mv.move_type() == MoveType::Castling && position.ruleset() == Ruleset::Standard {
+        let side = if origin.file() < destination.file() {
+            CastlingSide::King
+        } else {
+            CastlingSide::Queen
+        };
+
+        destination = CastlingVariety::new(position.turn(), side).king_destination();
+    }
+
+    let mut uci = format!("{}{}", square(origin), square(destination));
+
+    if mv.move_type() == MoveType::Promotion {
+        uci.push(char::from(Color::Black | mv.promotion()));
+    }
+
+    uci
+}
+
+fn square(square: Square) -> String {
+    format!("{}{}",
+        char::from(square.file()).to_ascii_lowercase(),
+        char::from(square.rank()),
+    )
+}
@@ -85,10 +85,37 @@
 #![feature(strict_provenance)]
 
 mod position;
+mod search;
+mod tt;
+mod value;
 
 pub mod prelude {
     #[doc(no_inline)]
     pub use crate::position::Position;
+
+    #[doc(no_inline)]
+    pub use crate::position::{FenError, FenField};
+
+    #[doc(no_inline)]
+    pub use crate::position::Invariant;
+
+    #[doc(no_inline)]
+    pub use crate::position::PositionError;
+
+    #[doc(no_inline)]
+    pub use crate::position::{GenType, MoveList};
+
+    #[doc(no_inline)]
+    pub use crate::position::{GameOutcome, Termination};
+
+    #[doc(no_inline)]
+    pub use crate::search::{Limits, Search, SearchResult, MAX_PLY};
+
+    #[doc(no_inline)]
+    pub use crate::tt::{Bound, TranspositionTable, TtEntry};
+
+    #[doc(no_inline)]
+    pub use crate::value::Value;
 }
 
 pub use prelude::*;
@@ -1,6 +1,30 @@
+mod atomic;
+mod check;
+mod chess960;
+mod do_move;
+mod draw;
 mod fen;
+mod invariants;
+mod legality;
+mod movegen;
+mod outcome;
+mod perft;
+mod see;
+mod state;
+mod transform;
+mod validation;
+
+pub use fen::{FenError, FenField};
+pub use invariants::Invariant;
+pub use movegen::{GenType, MoveList};
+pub use outcome::{GameOutcome, Termination};
+pub use validation::PositionError;
+
+use state::{Keys, State};
 
 use stockfish_core::prelude::*;
+use stockfish_core::accelerate;
+use stockfish_core::hash::{Key, Zobrist, ZOBRIST};
 
 use core::ops::Index;
 
@@ -12,13 +36,17 @@ pub struct Position {
     ruleset:   Ruleset,
     turn:      Color,
     board:     Board,
-    ply:       u8,
-    halfmoves: u8,
+    ply:       u16,
 
     // castling state
     castling_paths:     [Option<CastlingPath>; CastlingVariety::COUNT],
     castling_by_square: [CastlingRights; Square::COUNT],
 
+    // crazyhouse state: the pieces each side holds in hand, and the pieces on
+    // the board that were promoted from pawns
+    pockets:  [u8; Piece::COUNT],
+    promoted: Bitboard,
+
     // internal bitboards
     bb_all:      Bitboard,
     bb_by_color: [Bitboard; Color::COUNT],
@@ -28,10 +56,10 @@ pub struct Position {
     count_by_color: [u8; Color::COUNT],
     count_by_token: [u8; Piece::COUNT],
 
-    // TODO: stuff from the StateInfo stockfish struct that eventually doesn't
-    // go here
-    castling_rights: CastlingRights,
-    en_passant:      Option<Square>,
+    // irreversible state for the current position, along with that of every
+    // position leading up to it
+    state:   State,
+    history: Vec<State>,
 }
 
 #[allow(clippy::multiple_inherent_impl)]
@@ -42,11 +70,13 @@ impl Position {
             turn:      Color::White,
             board:     Board::EMPTY,
             ply:       0,
-            halfmoves: 0,
 
             castling_paths:     Default::default(),
             castling_by_square: [CastlingRights::default(); Square::COUNT],
 
+            pockets:  [0; Piece::COUNT],
+            promoted: Bitboard::EMPTY,
+
             bb_all:      Bitboard::EMPTY,
             bb_by_color: [Bitboard::EMPTY; Color::COUNT],
             bb_by_piece: [Bitboard::EMPTY; Token::COUNT],
@@ -54,8 +84,8 @@ impl Position {
             count_by_token: [0; Piece::COUNT],
             count_by_color: [0; Color::COUNT],
 
-            castling_rights: CastlingRights::NONE,
-            en_passant:      None,
+            state:   State::empty(),
+            history: Vec::new(),
         }
     }
 
@@ -67,11 +97,16 @@ impl Position {
         self.bb_by_color[piece.color()] |= square;
         self.bb_by_piece[piece.token()] |= square;
 
+        self.state.keys.toggle_piece(piece, square);
+        self.state.keys.toggle_material(piece, self.count_by_token[piece]);
+
         self.count_by_color[piece.color()] += 1;
         self.count_by_token[piece]         += 1;
 
         // TODO: piece-square tables
         // psq += PSQT::psq[pc][s];
+
+        debug_assert_eq!(Ok(()), self.validate_board_invariants());
     }
 
     #[inline]
@@ -85,12 +120,106 @@ impl Position {
         self.count_by_color[piece.color()] -= 1;
         self.count_by_token[piece]         -= 1;
 
+        self.state.keys.toggle_piece(piece, square);
+        self.state.keys.toggle_material(piece, self.count_by_token[piece]);
+
         // TODO: piece-square tables
         // psq -= PSQT::psq[pc][s];
 
+        debug_assert_eq!(Ok(()), self.validate_board_invariants());
+
         Some(piece)
     }
 
+    /// Adds a `piece` to its owner's pocket, from which it may later be
+    /// dropped onto the board.
+    #[inline]
+    pub(crate) fn add_to_pocket(&mut self, piece: Piece) {
+        debug_assert!(usize::from(self.pockets[piece]) < Zobrist::POCKET_CAPACITY,
+            "too many pieces in the pocket");
+
+        self.state.keys.toggle_pocket(piece, self.pockets[piece]);
+        self.pockets[piece] += 1;
+    }
+
+    /// Takes a `piece` out of its owner's pocket in order to drop it onto the
+    /// board.
+    #[inline]
+    pub(crate) fn take_from_pocket(&mut self, piece: Piece) {
+        debug_assert!(self.pockets[piece] > 0, "the piece must be in the pocket");
+
+        self.pockets[piece] -= 1;
+        self.state.keys.toggle_pocket(piece, self.pockets[piece]);
+    }
+
+    #[inline]
+    pub const fn turn(&self) -> Color {
+        self.turn
+    }
+
+    #[inline]
+    pub const fn ruleset(&self) -> Ruleset {
+        self.ruleset
+    }
+
+    /// Returns the number of plies played since the start of the game.
+    #[inline]
+    #[must_use]
+    pub const fn ply(&self) -> u16 {
+        self.ply
+    }
+
+    /// Returns the number of plies since the last capture or pawn move, for
+    /// the purposes of the fifty-move rule.
+    #[inline]
+    #[must_use]
+    pub const fn halfmoves(&self) -> u8 {
+        self.state.halfmoves
+    }
+
+    /// Returns the castling rights that have not yet been lost by either side.
+    #[inline]
+    pub const fn castling_rights(&self) -> CastlingRights {
+        self.state.castling_rights
+    }
+
+    /// Returns the square a pawn may capture onto en passant, if any.
+    #[inline]
+    #[must_use]
+    pub const fn en_passant(&self) -> Option<Square> {
+        self.state.en_passant
+    }
+
+    /// Returns the number of `piece` held in its owner's pocket, waiting to be
+    /// dropped onto the board. This is always zero outside of crazyhouse.
+    #[inline]
+    #[must_use]
+    pub fn pocket(&self, piece: Piece) -> u8 {
+        self.pockets[piece]
+    }
+
+    /// Returns the pieces on the board that were promoted from pawns, which
+    /// revert to pawns when captured. These are only tracked in crazyhouse.
+    #[inline]
+    pub const fn promoted(&self) -> Bitboard {
+        self.promoted
+    }
+
+    /// Returns the number of checks given so far by `color`. This is always
+    /// zero outside of three-check.
+    #[inline]
+    #[must_use]
+    pub fn checks_given(&self, color: Color) -> u8 {
+        self.state.checks[color]
+    }
+
+    /// Returns the piece captured by the last move made, if any.
+    #[inline]
+    #[must_use]
+    pub const fn captured(&self) -> Option<Piece> {
+        self.state.captured
+    }
+
     #[inline]
     pub const fn bitboard(&self) -> Bitboard {
         self.bb_all
@@ -110,6 +239,245 @@ impl Position {
     pub fn bitboard_for_token(&self, piece: Piece) -> Bitboard {
         self.bb_by_color[piece.color()] & self.bb_by_piece[piece.token()]
     }
+
+    /// Returns the Zobrist key uniquely identifying this position.
+    #[inline]
+    pub const fn key(&self) -> Key {
+        self.state.keys.position
+    }
+
+    /// Returns the Zobrist key identifying the pawn structure of this
+    /// position.
+    #[inline]
+    pub const fn pawn_key(&self) -> Key {
+        self.state.keys.pawns
+    }
+
+    /// Returns the Zobrist key identifying the material balance of this
+    /// position, regardless of where the pieces are.
+    #[inline]
+    pub const fn material_key(&self) -> Key {
+        self.state.keys.material
+    }
+
+    /// Returns the Zobrist key identifying the placement of all the non-pawn
+    /// pieces of the given `color`.
+    #[inline]
+    pub fn non_pawn_key(&self, color: Color) -> Key {
+        self.state.keys.non_pawns[color]
+    }
+
+    /// Returns the square the king of the given `color` is on, if there is
+    /// one. In antichess, where a side may have several kings, this is
+    /// whichever of them is on the lowest square.
+    #[inline]
+    #[must_use]
+    pub fn king_square(&self, color: Color) -> Option<Square> {
+        self.bitboard_for_token(color | Token::King).into()
+    }
+
+    /// Returns the castling path for the given `variety`, if castling of that
+    /// variety was ever possible in this game.
+    #[inline]
+    pub fn castling_path(&self, variety: CastlingVariety) -> Option<CastlingPath> {
+        self.castling_paths[variety]
+    }
+
+    /// Returns [`true`] if the side owning the given castling `rights` has not
+    /// yet lost them.
+    #[inline]
+    #[must_use]
+    pub fn can_castle(&self, rights: CastlingRights) -> bool {
+        self.state.castling_rights.intersects(rights)
+    }
+
+    /// Returns [`true`] if `mv` captures an enemy piece, whether on its
+    /// destination or en passant.
+    #[inline]
+    #[must_use]
+    pub fn is_capture(&self, mv: Move) -> bool {
+        mv.move_type() == MoveType::EnPassant
+            || self.bitboard_for_color(!self.turn).contains(mv.destination())
+    }
+
+    /// Returns [`true`] if any square the king or rook would need to pass
+    /// through in order to castle along `path` is occupied.
+    #[inline]
+    #[must_use]
+    pub const fn is_castling_impeded(&self, path: CastlingPath) -> bool {
+        self.bb_all.overlaps(path.path())
+    }
+
+    /// Returns a bitboard of every piece, of either color, that attacks the
+    /// given `square` assuming the board has the given `occupancy`.
+    #[inline]
+    pub fn attackers_to(&self, square: Square, occupancy: Bitboard) -> Bitboard {
+        let rooks   = self.bb_by_piece[Token::Rook]   | self.bb_by_piece[Token::Queen];
+        let bishops = self.bb_by_piece[Token::Bishop] | self.bb_by_piece[Token::Queen];
+
+        // a pawn of one color on `square` attacks exactly those squares that a
+        // pawn of the other color would need to be on to attack it
+        (Piece::BlackPawn.attacks(square, occupancy) & self.bitboard_for_token(Piece::WhitePawn)) |
+        (Piece::WhitePawn.attacks(square, occupancy) & self.bitboard_for_token(Piece::BlackPawn)) |
+
+        (Token::Knight.attacks(square, occupancy) & self.bb_by_piece[Token::Knight]) |
+        (Token::Rook  .attacks(square, occupancy) & rooks)                          |
+        (Token::Bishop.attacks(square, occupancy) & bishops)                        |
+        (Token::King  .attacks(square, occupancy) & self.bb_by_piece[Token::King])
+    }
+
+    /// Returns a bitboard of the enemy pieces currently giving check to the
+    /// king of the side to move.
+    #[inline]
+    pub const fn checkers(&self) -> Bitboard {
+        self.state.checkers
+    }
+
+    /// Returns the pieces, of either color, that are the only thing standing
+    /// between the king of the given `color` and an enemy slider. Moving one
+    /// of these pieces will either expose the king to check (if it belongs to
+    /// `color`) or give a discovered check (if it belongs to the opponent).
+    #[inline]
+    pub fn blockers_for_king(&self, color: Color) -> Bitboard {
+        self.state.blockers[color]
+    }
+
+    /// Returns the sliders of the given `color` that are pinning a piece to
+    /// the opponent's king.
+    #[inline]
+    pub fn pinners(&self, color: Color) -> Bitboard {
+        self.state.pinners[color]
+    }
+
+    /// Returns the squares from which a `token` belonging to the side to move
+    /// would give check to the enemy king.
+    #[inline]
+    pub fn check_squares(&self, token: Token) -> Bitboard {
+        self.state.check_squares[token]
+    }
+
+    /// Returns the pieces belonging to the side to move that are the only
+    /// thing standing between one of their own sliders and the enemy king.
+    /// Moving one of these pieces off of that line gives a discovered check.
+    #[inline]
+    pub fn discovered_check_candidates(&self) -> Bitboard {
+        self.state.blockers[!self.turn] & self.bb_by_color[self.turn]
+    }
+
+    /// Recomputes the checkers, blockers, pinners, and check squares for the
+    /// current board.
+    ///
+    /// This is *not* done automatically by [`Position::emplace`] or
+    /// [`Position::remove`], since it's wasteful to do so while the board is
+    /// being built up one piece at a time.
+    pub(crate) fn update_check_info(&mut self) {
+        for color in Color::iter() {
+            let (blockers, pinners) = self.king_square(color).map_or(
+                (Bitboard::EMPTY, Bitboard::EMPTY),
+                |king| self.slider_blockers(self.bb_by_color[!color], king),
+            );
+
+            self.state.blockers[color] = blockers;
+            self.state.pinners[!color] = pinners;
+        }
+
+        self.state.checkers = self.attackers_of_king(self.turn);
+
+        // a piece gives check from exactly those squares that the same type
+        // of piece could reach starting from the king
+        let them = !self.turn;
+
+        self.state.check_squares = self.king_square(them).map_or([Bitboard::EMPTY; Token::COUNT], |king| {
+            let bishop = Token::Bishop.attacks(king, self.bb_all);
+            let rook   = Token::Rook  .attacks(king, self.bb_all);
+
+            [
+                (them | Token::Pawn).attacks(king, self.bb_all),
+                Token::Knight.attacks(king, self.bb_all),
+                bishop,
+                rook,
+                bishop | rook,
+                Bitboard::EMPTY,
+            ]
+        });
+    }
+
+    /// Computes every Zobrist key for the position from scratch.
+    ///
+    /// The keys are normally kept updated incrementally as the board changes,
+    /// so this is only needed when setting up a new position and to verify
+    /// the incremental keys in debug builds.
+    pub(crate) fn compute_keys(&self) -> Keys {
+        let mut keys = Keys {
+            position: ZOBRIST.castling_key(self.state.castling_rights),
+            ..Keys::empty()
+        };
+
+        for (square, piece) in self.board.iter() {
+            keys.toggle_piece(piece, square);
+        }
+
+        for piece in Piece::iter() {
+            for count in 0..self.count_by_token[piece] {
+                keys.toggle_material(piece, count);
+            }
+
+            for count in 0..self.pockets[piece] {
+                keys.toggle_pocket(piece, count);
+            }
+        }
+
+        for color in Color::iter() {
+            for count in 0..self.state.checks[color] {
+                keys.toggle_checks(color, count);
+            }
+        }
+
+        keys.toggle_en_passant(self.state.en_passant);
+
+        if self.turn.is_black() {
+            keys.toggle_side();
+        }
+
+        keys
+    }
+
+    /// Returns the pieces (of either color) that are the only thing standing
+    /// between `square` and one of the given `sliders`, along with the sliders
+    /// pinning a piece of the same color as the piece on `square`.
+    ///
+    /// The pieces returned are the ones that, if moved, would expose `square`
+    /// to attack.
+    pub(crate) fn slider_blockers(&self, sliders: Bitboard, square: Square) -> (Bitboard, Bitboard) {
+        let mut blockers = Bitboard::EMPTY;
+        let mut pinners  = Bitboard::EMPTY;
+
+        // snipers are the sliders that would attack `square` if every piece
+        // between them were removed
+        let rooks   = self.bb_by_piece[Token::Rook]   | self.bb_by_piece[Token::Queen];
+        let bishops = self.bb_by_piece[Token::Bishop] | self.bb_by_piece[Token::Queen];
+        let snipers = sliders & (
+            (Token::Rook  .moves(square) & rooks) |
+            (Token::Bishop.moves(square) & bishops)
+        );
+
+        let occupancy = self.bb_all ^ snipers;
+        let color     = self.board[square].map(Piece::color);
+
+        for sniper in snipers {
+            let between = accelerate::between(square, sniper) & occupancy;
+
+            if between.is_one() {
+                blockers |= between;
+
+                if color.map_or(false, |c| between.overlaps(self.bb_by_color[c])) {
+                    pinners |= sniper;
+                }
+            }
+        }
+
+        (blockers, pinners)
+    }
 }
 
 impl Index<Square> for Position {
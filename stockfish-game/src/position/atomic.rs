@@ -0,0 +1,264 @@
+use crate::prelude::*;
+use stockfish_core::prelude::*;
+use stockfish_core::accelerate;
+
+#[allow(clippy::multiple_inherent_impl)]
+impl Position {
+    /// Returns the squares caught in an atomic explosion centered on
+    /// `square`: the square itself, along with every surrounding square that
+    /// isn't occupied by a pawn.
+    pub(crate) fn blast(&self, square: Square) -> Bitboard {
+        (Token::King.moves(square) & !self.bb_by_piece[Token::Pawn]) | square
+    }
+
+    /// Returns [`true`] if the pseudo-legal move `mv` is legal under the rules
+    /// of atomic chess.
+    ///
+    /// A move may never blow up the moving side's own king. Blowing up the
+    /// enemy king wins the game on the spot, so such a move is legal even if
+    /// it would otherwise leave the king in check. And since a king can't be
+    /// captured without destroying the king next to it, a king touching the
+    /// enemy king can never be in check.
+    pub(crate) fn is_legal_atomic(&self, mv: Move) -> bool {
+        let us          = self.turn;
+        let origin      = mv.origin();
+        let destination = mv.destination();
+
+        // with the king already gone, the game is over
+        let Some(king) = self.king_square(us) else { return false };
+
+        let occupancy = self.occupancy_after_atomic(mv);
+
+        if mv.move_type() == MoveType::Castling {
+            let (king_destination, _) = Self::castling_destinations(us, origin, destination);
+
+            // the king may not pass through check on its way, and can't be
+            // left in check once it arrives
+            return accelerate::between(origin, king_destination).iter()
+                .all(|square| self.is_safe_atomic(square, self.bitboard()))
+                && self.is_safe_atomic(king_destination, occupancy);
+        }
+
+        let king = if origin == king { destination } else { king };
+
+        if !occupancy.contains(king) {
+            return false;
+        }
+
+        if !occupancy.overlaps(self.bitboard_for_token(!us | Token::King)) {
+            return true;
+        }
+
+        self.is_safe_atomic(king, occupancy)
+    }
+
+    /// Returns [`true`] if the pseudo-legal move `mv` would put the enemy king
+    /// in check under the rules of atomic chess.
+    ///
+    /// Moves which blow up the enemy king end the game rather than giving
+    /// check, and a king can't be checked while touching the enemy king.
+    pub(crate) fn gives_check_atomic(&self, mv: Move) -> bool {
+        let us          = self.turn;
+        let origin      = mv.origin();
+        let destination = mv.destination();
+        let occupancy   = self.occupancy_after_atomic(mv);
+
+        let Some(king) = self.king_square(!us).filter(|&king| occupancy.contains(king)) else {
+            return false;
+        };
+
+        // the king and whichever piece moved are the only ones of ours that
+        // might not be where the bitboards say they are
+        let (our_king, moved) = match mv.move_type() {
+            MoveType::Castling => {
+                let (king_destination, rook_destination) =
+                    Self::castling_destinations(us, origin, destination);
+
+                (Some(king_destination), Some((us | Token::Rook, rook_destination)))
+            },
+
+            MoveType::Promotion => (
+                self.king_square(us),
+                Some((us | mv.promotion(), destination)),
+            ),
+
+            _ => (
+                self.king_square(us).map(|square| if square == origin { destination } else { square }),
+                self[origin].map(|piece| (piece, destination)),
+            ),
+        };
+
+        if our_king.map_or(false, |our_king| Token::King.moves(our_king).contains(king)) {
+            return false;
+        }
+
+        let attackers = self.attackers_to(king, occupancy)
+            & self.bitboard_for_color(us)
+            & occupancy;
+
+        // a capturing piece never survives its own explosion
+        attackers.is_any() || moved
+            .filter(|&(_, square)| occupancy.contains(square))
+            .map_or(false, |(piece, square)| piece.attacks(square, occupancy).contains(king))
+    }
+
+    /// Returns [`true`] if the side to move's king would be safe from capture
+    /// on `square` given the `occupancy` of the board, where only pieces
+    /// still present in `occupancy` are able to attack it.
+    fn is_safe_atomic(&self, square: Square, occupancy: Bitboard) -> bool {
+        let enemies = self.bitboard_for_color(!self.turn) & occupancy;
+
+        Token::King.moves(square).overlaps(enemies & self.bitboard_for_piece(Token::King))
+            || (self.attackers_to(square, occupancy) & enemies).is_empty()
+    }
+
+    /// Returns the occupancy of the board after making the pseudo-legal move
+    /// `mv`, including the effects of any explosion.
+    fn occupancy_after_atomic(&self, mv: Move) -> Bitboard {
+        let us          = self.turn;
+        let origin      = mv.origin();
+        let destination = mv.destination();
+        let occupancy   = self.bitboard() ^ origin;
+
+        let captured = match mv.move_type() {
+            MoveType::Castling => {
+                let (king_destination, rook_destination) =
+                    Self::castling_destinations(us, origin, destination);
+
+                return (occupancy ^ destination) | king_destination | rook_destination;
+            },
+
+            MoveType::EnPassant => Some(destination.wrapping_sub(us.direction())),
+
+            _ => Some(destination).filter(|&square| {
+                self.bitboard_for_color(!us).contains(square)
+            }),
+        };
+
+        match captured {
+            Some(captured) => occupancy & !(self.blast(destination) | captured),
+            None           => occupancy | destination,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn explosion() {
+        let mut position = Position::from_fen(Ruleset::Atomic,
+            b"rnbqkbnr/ppp1pppp/8/3p4/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 0 1");
+
+        // the capturing pawn is destroyed along with the captured one
+        position.do_move(Move::new(Square::E4, Square::D5));
+
+        assert_eq!("rnbqkbnr/ppp1pppp/8/8/8/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1", position.to_fen());
+
+        // the queen takes the pawn on f2, blowing up the king and minor
+        // pieces next to it, but not the pawn on g2
+        let mut position = Position::from_fen(Ruleset::Atomic,
+            b"rnb1kbnr/pppp1ppp/8/4p3/4P2q/8/PPPPKPPP/RNBQ1BNR b kq - 0 1");
+
+        position.do_move(Move::new(Square::H4, Square::F2));
+
+        assert_eq!(None, position.king_square(Color::White));
+        assert_eq!("rnb1kbnr/pppp1ppp/8/4p3/4P3/8/PPPP2PP/RNBQ3R w kq - 0 2", position.to_fen());
+    }
+
+    #[test]
+    fn explosion_forfeits_castling() {
+        let mut position = Position::from_fen(Ruleset::Atomic,
+            b"r3k2r/8/8/3b4/8/8/6P1/R3K2R b KQkq - 0 1");
+
+        // the rook on h1 is caught in the blast, taking white's right to
+        // castle kingside with it
+        position.do_move(Move::new(Square::D5, Square::G2));
+
+        assert_eq!(None, position[Square::H1]);
+        assert_eq!(CastlingRights::WHITE_OOO | CastlingRights::BLACK, position.castling_rights());
+    }
+
+    #[test]
+    fn kings_cannot_capture() {
+        let position = Position::from_fen(Ruleset::Atomic,
+            b"4k3/8/8/8/8/8/4p3/4K3 w - - 0 1");
+
+        let moves = position.legal_moves();
+
+        assert!(!moves.contains(&Move::new(Square::E1, Square::E2)));
+        assert!( moves.contains(&Move::new(Square::E1, Square::D2)));
+    }
+
+    #[test]
+    fn adjacent_kings() {
+        // the kings touch, so the rook attacking the white king isn't giving
+        // check
+        let position = Position::from_fen(Ruleset::Atomic,
+            b"8/8/8/8/8/4k3/4K3/4r3 w - - 0 1");
+
+        assert!(position.checkers().is_empty());
+
+        // and the king may step onto an attacked square, as long as it ends
+        // up next to the enemy king
+        let position = Position::from_fen(Ruleset::Atomic,
+            b"8/8/8/8/4k3/8/4K3/3r4 w - - 0 1");
+
+        let moves = position.legal_moves();
+
+        assert!( moves.contains(&Move::new(Square::E2, Square::D3)));
+        assert!(!moves.contains(&Move::new(Square::E2, Square::D2)));
+    }
+
+    #[test]
+    fn blowing_up_own_king() {
+        // capturing the pawn on d2 would destroy the white king as well
+        let position = Position::from_fen(Ruleset::Atomic,
+            b"4k3/8/8/8/8/8/3p4/3QK3 w - - 0 1");
+
+        let moves = position.legal_moves();
+
+        assert!(!moves.contains(&Move::new(Square::D1, Square::D2)));
+        assert!( moves.contains(&Move::new(Square::E1, Square::E2)));
+    }
+
+    #[test]
+    fn blowing_up_enemy_king_while_in_check() {
+        let position = Position::from_fen(Ruleset::Atomic,
+            b"4k3/3p4/8/8/B6q/8/8/4K3 w - - 0 1");
+
+        let moves = position.legal_moves();
+
+        // the queen is still checking the white king afterwards, but taking
+        // the pawn on d7 blows up the black king and wins on the spot
+        assert!(position.checkers().is_any());
+        assert!( moves.contains(&Move::new(Square::A4, Square::D7)));
+        assert!(!moves.contains(&Move::new(Square::A4, Square::B5)));
+    }
+
+    #[test]
+    fn gives_check_adjacent_kings() {
+        let knight = Move::new(Square::H3, Square::G5);
+
+        let position = Position::from_fen(Ruleset::Atomic,
+            b"8/8/4k3/8/8/7N/8/3K4 w - - 0 1");
+
+        assert!(position.gives_check(knight));
+
+        // a king next to the enemy king can't be in check
+        let position = Position::from_fen(Ruleset::Atomic,
+            b"8/8/4k3/3K4/8/7N/8/8 w - - 0 1");
+
+        assert!(!position.gives_check(knight));
+    }
+
+    #[test]
+    fn gives_check_by_explosion() {
+        // blowing up the knight on e5 uncovers the rook
+        let position = Position::from_fen(Ruleset::Atomic,
+            b"4k3/8/8/4n3/3p4/8/1B6/K3R3 w - - 0 1");
+
+        assert!(position.gives_check(Move::new(Square::B2, Square::D4)));
+    }
+}
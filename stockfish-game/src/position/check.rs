@@ -0,0 +1,246 @@
+use crate::prelude::*;
+use stockfish_core::prelude::*;
+use stockfish_core::accelerate;
+
+#[allow(clippy::multiple_inherent_impl)]
+impl Position {
+    /// Returns [`true`] if making the pseudo-legal move `mv` would put the
+    /// enemy king in check, without having to make the move.
+    ///
+    /// Checks may be given directly by the moving or dropped piece (or the
+    /// piece it promotes to), by discovery when the moving piece steps off the
+    /// line between one of our sliders and the enemy king, by discovery
+    /// through the pawn captured en passant, or by the rook after castling.
+    /// In atomic chess, a capture may also uncover a check by blowing up the
+    /// pieces in the way. There's no such thing as check in antichess.
+    #[must_use]
+    pub fn gives_check(&self, mv: Move) -> bool {
+        if self.ruleset == Ruleset::Antichess {
+            return false;
+        }
+
+        if self.ruleset == Ruleset::Atomic {
+            return self.gives_check_atomic(mv);
+        }
+
+        let us          = self.turn;
+        let origin      = mv.origin();
+        let destination = mv.destination();
+
+        let Some(king) = self.king_square(!us) else { return false };
+
+        // a dropped piece can't uncover anything, so it can only give check
+        // directly
+        if mv.move_type() == MoveType::Drop {
+            return self.state.check_squares[mv.dropped()].contains(destination);
+        }
+
+        let Some(piece) = self[origin] else { return false };
+
+        debug_assert_eq!(us, piece.color(),
+            "the moving piece must belong to the side to move");
+
+        if mv.move_type() == MoveType::Castling {
+            return self.gives_check_castling(origin, destination, king);
+        }
+
+        if self.state.check_squares[piece.token()].contains(destination) {
+            return true;
+        }
+
+        if self.discovered_check_candidates().contains(origin)
+            && !accelerate::line(origin, destination).contains(king)
+        {
+            return true;
+        }
+
+        match mv.move_type() {
+            MoveType::Promotion => mv.promotion()
+                .attacks(destination, self.bitboard() ^ origin)
+                .contains(king),
+
+            // the captured pawn may have been the only thing shielding the
+            // king from one of our sliders, which can't be detected by the
+            // blockers since two pieces leave the line at once
+            MoveType::EnPassant => {
+                let captured  = destination.wrapping_sub(us.direction());
+                let occupancy = (self.bitboard() ^ origin ^ captured) | destination;
+
+                self.sliders_attacking(king, occupancy, self.bitboard_for_color(us)).is_any()
+            },
+
+            _ => false,
+        }
+    }
+
+    /// Returns [`true`] if castling the king on `origin` with the rook on
+    /// `rook` checks the enemy `king`.
+    fn gives_check_castling(&self, origin: Square, rook: Square, king: Square) -> bool {
+        let (king_destination, rook_destination) =
+            Self::castling_destinations(self.turn, origin, rook);
+
+        let occupancy = (self.bitboard() ^ origin ^ rook) | king_destination | rook_destination;
+        let ours      = self.bitboard_for_color(self.turn) ^ origin ^ rook;
+
+        // the rook is the only piece that can give check directly, but in
+        // Chess960 the king may also step out of the way of another slider
+        Token::Rook.attacks(rook_destination, occupancy).contains(king)
+            || self.sliders_attacking(king, occupancy, ours).is_any()
+    }
+
+    /// Returns the bishops, rooks, and queens among `pieces` attacking
+    /// `square` with the given `occupancy`.
+    fn sliders_attacking(&self, square: Square, occupancy: Bitboard, pieces: Bitboard) -> Bitboard {
+        let queens  = self.bitboard_for_piece(Token::Queen);
+        let rooks   = self.bitboard_for_piece(Token::Rook)   | queens;
+        let bishops = self.bitboard_for_piece(Token::Bishop) | queens;
+
+        pieces & (
+            (Token::Rook  .attacks(square, occupancy) & rooks) |
+            (Token::Bishop.attacks(square, occupancy) & bishops)
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Ensures that [`Position::gives_check`] agrees with actually making
+    /// every legal move in the position.
+    fn assert_gives_check(ruleset: Ruleset, fen: &str) {
+        let mut position = Position::from_fen(ruleset, fen.as_bytes());
+
+        for mv in &position.clone().legal_moves() {
+            let gives_check = position.gives_check(*mv);
+
+            position.do_move(*mv);
+            assert_eq!(position.checkers().is_any(), gives_check, "{mv:?} in {fen}");
+            position.undo_move(*mv);
+        }
+    }
+
+    #[test]
+    fn gives_check_agrees_with_do_move() {
+        for fen in [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+            "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+        ] {
+            assert_gives_check(Ruleset::Standard, fen);
+        }
+
+        assert_gives_check(Ruleset::Chess960,
+            "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9");
+        assert_gives_check(Ruleset::Chess960,
+            "1nbbnrkr/p1p1ppp1/3p4/1p3P1p/3Pq2P/8/PPP1P1P1/QNBBNRKR w HFhf - 0 9");
+        assert_gives_check(Ruleset::Crazyhouse,
+            "2k5/8/8/8/8/8/8/4K3[QRBNPqrbnp] w - - 0 1");
+        assert_gives_check(Ruleset::Crazyhouse,
+            "r1bqk2r/pppp1ppp/2n1p3/4P3/1b1Pn3/2NB1N2/PPP2PPP/R1BQK2R[QRBNP] w KQkq - 0 1");
+        assert_gives_check(Ruleset::Atomic,
+            "rn2kb1r/1pp1p2p/p2q1pp1/3P4/2P3b1/4PN2/PP3PPP/R2QKB1R b KQkq - 0 1");
+        assert_gives_check(Ruleset::Atomic,
+            "4k3/8/8/4n3/3p4/8/1B6/K3R3 w - - 0 1");
+        assert_gives_check(Ruleset::Antichess,
+            "rnbqkbnr/ppp1pppp/8/3p4/8/2N5/PPPPPPPP/R1BQKBNR w - - 0 2");
+    }
+
+    #[test]
+    fn gives_check_direct() {
+        let position = Position::from_fen(Ruleset::Standard,
+            b"4k3/8/8/8/8/8/8/3QK3 w - - 0 1");
+
+        assert!(position.gives_check(Move::new(Square::D1, Square::D8)));
+
+        let position = Position::from_fen(Ruleset::Standard,
+            b"4k3/8/8/8/8/8/8/1N2K3 w - - 0 1");
+
+        assert!(!position.gives_check(Move::new(Square::B1, Square::C3)));
+
+        let position = Position::from_fen(Ruleset::Standard,
+            b"4k3/8/8/3P4/8/8/8/4K3 w - - 0 1");
+
+        assert!(!position.gives_check(Move::new(Square::D5, Square::D6)));
+
+        let position = Position::from_fen(Ruleset::Standard,
+            b"4k3/8/3P4/8/8/8/8/4K3 w - - 0 1");
+
+        assert!(position.gives_check(Move::new(Square::D6, Square::D7)));
+    }
+
+    #[test]
+    fn gives_check_discovered() {
+        // the bishop steps off the e-file, uncovering the rook
+        let position = Position::from_fen(Ruleset::Standard,
+            b"4k3/8/8/8/4B3/8/8/4RK2 w - - 0 1");
+
+        assert!(position.gives_check(Move::new(Square::E4, Square::C6)));
+
+        // but a piece can't discover a check by moving along the same line,
+        // since it remains in the way
+        let position = Position::from_fen(Ruleset::Standard,
+            b"4k3/8/8/8/4R3/8/8/4QK2 w - - 0 1");
+
+        assert!(!position.gives_check(Move::new(Square::E4, Square::E3)));
+    }
+
+    #[test]
+    fn gives_check_promotion() {
+        let position = Position::from_fen(Ruleset::Standard,
+            b"3k4/6P1/8/8/8/8/8/4K3 w - - 0 1");
+
+        assert!( position.gives_check(Move::new_promote_queen (Square::G7, Square::G8)));
+        assert!( position.gives_check(Move::new_promote_rook  (Square::G7, Square::G8)));
+        assert!(!position.gives_check(Move::new_promote_bishop(Square::G7, Square::G8)));
+        assert!(!position.gives_check(Move::new_promote_knight(Square::G7, Square::G8)));
+
+        // the promoted piece sees through the square the pawn left behind
+        let position = Position::from_fen(Ruleset::Standard,
+            b"8/6P1/8/8/8/6k1/8/K7 w - - 0 1");
+
+        assert!(position.gives_check(Move::new_promote_rook(Square::G7, Square::G8)));
+
+        let position = Position::from_fen(Ruleset::Standard,
+            b"6k1/8/8/8/8/8/1p6/R3K3 b - - 0 1");
+
+        assert!(position.gives_check(Move::new_promote_queen(Square::B2, Square::B1)));
+    }
+
+    #[test]
+    fn gives_check_en_passant_discovered() {
+        // capturing en passant removes both pawns from the fifth rank,
+        // uncovering the rook
+        let position = Position::from_fen(Ruleset::Standard,
+            b"8/8/8/k2pP2R/8/8/8/4K3 w - d6 0 1");
+
+        assert!(position.gives_check(Move::new_en_passant(Square::E5, Square::D6)));
+
+        let position = Position::from_fen(Ruleset::Standard,
+            b"8/8/8/3pP2R/k7/8/8/4K3 w - d6 0 1");
+
+        assert!(!position.gives_check(Move::new_en_passant(Square::E5, Square::D6)));
+    }
+
+    #[test]
+    fn gives_check_castling() {
+        // the rook lands on f1, checking the king on f8
+        let position = Position::from_fen(Ruleset::Standard,
+            b"5k2/8/8/8/8/8/8/4K2R w K - 0 1");
+
+        assert!(position.gives_check(Move::new_castling(Square::E1, Square::H1)));
+
+        let position = Position::from_fen(Ruleset::Standard,
+            b"4k3/8/8/8/8/8/8/4K2R w K - 0 1");
+
+        assert!(!position.gives_check(Move::new_castling(Square::E1, Square::H1)));
+
+        let position = Position::from_fen(Ruleset::Standard,
+            b"3k4/8/8/8/8/8/8/R3K3 w Q - 0 1");
+
+        assert!(position.gives_check(Move::new_castling(Square::E1, Square::A1)));
+    }
+}
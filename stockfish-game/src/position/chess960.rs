@@ -0,0 +1,245 @@
+use crate::prelude::*;
+use stockfish_core::prelude::*;
+
+/// The number of distinct Chess960 starting positions.
+const CHESS960_POSITIONS: u16 = 960;
+
+/// The placement of both knights among the five squares left empty after the
+/// bishops and queen, for each of the ten possible values of that part of a
+/// Scharnagl index.
+const KNIGHTS: [(usize, usize); 10] = [
+    (0, 1), (0, 2), (0, 3), (0, 4),
+    (1, 2), (1, 3), (1, 4),
+    (2, 3), (2, 4),
+    (3, 4),
+];
+
+#[allow(clippy::multiple_inherent_impl)]
+impl Position {
+    /// Creates the Chess960 starting position with the given Scharnagl
+    /// `index`, where both sides have the same arrangement of pieces.
+    ///
+    /// Index 518 is the standard chess starting position.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` isn't in the range `0..960`.
+    pub fn chess960(index: u16) -> Self {
+        Self::double_chess960(index, index)
+    }
+
+    /// Creates the Double Fischer Random starting position with white's
+    /// pieces arranged according to the Scharnagl index `white` and black's
+    /// according to `black`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if either index isn't in the range `0..960`.
+    pub fn double_chess960(white: u16, black: u16) -> Self {
+        assert!(white < CHESS960_POSITIONS, "Chess960 index {white} out of range");
+        assert!(black < CHESS960_POSITIONS, "Chess960 index {black} out of range");
+
+        let mut board    = Board::EMPTY;
+        let mut castling: [Option<CastlingPath>; CastlingVariety::COUNT] = Default::default();
+
+        for (color, index) in [(Color::White, white), (Color::Black, black)] {
+            let back_rank = Self::chess960_back_rank(index);
+
+            for (file, token) in File::iter().zip(back_rank) {
+                board[Square::new(file, Rank::_1).from_perspective(color)] = Some(color | token);
+                board[Square::new(file, Rank::_2).from_perspective(color)] = Some(color | Token::Pawn);
+            }
+
+            let files = |token: Token| File::iter()
+                .zip(back_rank)
+                .filter(move |&(_, t)| t == token)
+                .map(|(file, _)| file);
+
+            // there's always exactly one king, with a rook to either side
+            let Some(king) = files(Token::King).next() else { continue };
+
+            for rook in files(Token::Rook) {
+                if let Some(path) = CastlingPath::new(color, king, rook) {
+                    castling[path.variety()] = Some(path);
+                }
+            }
+        }
+
+        Self::from_parts(Ruleset::Chess960, board, Color::White, castling, None, 0, 0)
+    }
+
+    /// Returns the Scharnagl index of the position if it's the Chess960
+    /// starting position with that index.
+    ///
+    /// The ruleset isn't taken into account, so the standard chess starting
+    /// position is recognized as index 518 regardless.
+    #[must_use]
+    pub fn chess960_index(&self) -> Option<u16> {
+        self.double_chess960_indexes()
+            .filter(|&(white, black)| white == black)
+            .map(|(white, _)| white)
+    }
+
+    /// Returns the Scharnagl indexes of white's and black's arrangements of
+    /// pieces if the position is the Double Fischer Random starting position
+    /// with those indexes.
+    #[must_use]
+    pub fn double_chess960_indexes(&self) -> Option<(u16, u16)> {
+        let index = |color: Color| -> Option<u16> {
+            let mut back_rank = [Token::Pawn; File::COUNT];
+
+            for (file, token) in File::iter().zip(&mut back_rank) {
+                *token = self.board[Square::new(file, Rank::_1).from_perspective(color)]
+                    .filter(|piece| piece.color() == color)?
+                    .token();
+            }
+
+            (0..CHESS960_POSITIONS).find(|&i| Self::chess960_back_rank(i) == back_rank)
+        };
+
+        let white    = index(Color::White)?;
+        let black    = index(Color::Black)?;
+        let expected = Self::double_chess960(white, black);
+
+        // the back ranks alone don't rule out the rest of the board having
+        // changed, nor any castling rights having been lost
+        let is_start = self.board         == expected.board
+            && self.turn                  == expected.turn
+            && self.castling_paths        == expected.castling_paths
+            && self.state.castling_rights == expected.state.castling_rights
+            && self.state.en_passant      == expected.state.en_passant;
+
+        is_start.then_some((white, black))
+    }
+
+    /// Returns the arrangement of pieces along the back rank, from the a-file
+    /// to the h-file, for the given Scharnagl `index`.
+    ///
+    /// The light-squared bishop is placed first, followed by the dark-squared
+    /// bishop, the queen, and the knights, each on one of the squares still
+    /// empty. The rooks and king take the three remaining squares, with the
+    /// king between the rooks.
+    // the index is unpacked digit by digit in a mixed radix
+    #[allow(clippy::integer_division)]
+    fn chess960_back_rank(index: u16) -> [Token; File::COUNT] {
+        let mut back_rank = [None; File::COUNT];
+        let mut index     = usize::from(index);
+
+        // the b, d, f, and h-files are the light squares on the first rank,
+        // and the a, c, e, and g-files are the dark squares
+        back_rank[index % 4 * 2 + 1] = Some(Token::Bishop);
+        index /= 4;
+
+        back_rank[index % 4 * 2] = Some(Token::Bishop);
+        index /= 4;
+
+        // places the token on the nth square that's still empty
+        let mut place = |nth: usize, token: Token| {
+            let file = back_rank.iter()
+                .enumerate()
+                .filter(|(_, t)| t.is_none())
+                .nth(nth)
+                .map(|(file, _)| file);
+
+            if let Some(file) = file {
+                back_rank[file] = Some(token);
+            }
+        };
+
+        place(index % 6, Token::Queen);
+        index /= 6;
+
+        // placing the second knight first keeps the first knight's square
+        // from moving
+        let (first, second) = KNIGHTS[index];
+
+        place(second, Token::Knight);
+        place(first,  Token::Knight);
+
+        place(0, Token::Rook);
+        place(0, Token::King);
+        place(0, Token::Rook);
+
+        back_rank.map(|token| token.unwrap_or(Token::Pawn))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chess960() {
+        assert_eq!("bbqnnrkr/pppppppp/8/8/8/8/PPPPPPPP/BBQNNRKR w HFhf - 0 1", Position::chess960(0).to_fen());
+        assert_eq!("bqnbnrkr/pppppppp/8/8/8/8/PPPPPPPP/BQNBNRKR w HFhf - 0 1", Position::chess960(1).to_fen());
+        assert_eq!("qbbnrnkr/pppppppp/8/8/8/8/PPPPPPPP/QBBNRNKR w HEhe - 0 1", Position::chess960(100).to_fen());
+        assert_eq!("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w HAha - 0 1", Position::chess960(518).to_fen());
+        assert_eq!("rkrnnqbb/pppppppp/8/8/8/8/PPPPPPPP/RKRNNQBB w CAca - 0 1", Position::chess960(959).to_fen());
+
+        assert_eq!(Ruleset::Chess960, Position::chess960(0).ruleset());
+    }
+
+    #[test]
+    fn chess960_matches_fen() {
+        let fen      = "bbqnnrkr/pppppppp/8/8/8/8/PPPPPPPP/BBQNNRKR w HFhf - 0 1";
+        let position = Position::from_fen(Ruleset::Chess960, fen.as_bytes());
+
+        assert_eq!(position,       Position::chess960(0));
+        assert_eq!(position.key(), Position::chess960(0).key());
+    }
+
+    #[test]
+    #[should_panic(expected = "out of range")]
+    fn chess960_out_of_range() {
+        let _ = Position::chess960(960);
+    }
+
+    #[test]
+    fn chess960_index() {
+        for index in 0..960 {
+            assert_eq!(Some(index), Position::chess960(index).chess960_index());
+        }
+
+        // the standard starting position is recognized under either ruleset
+        assert_eq!(Some(518), Position::from_fen(Ruleset::Standard,
+            b"rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").chess960_index());
+    }
+
+    #[test]
+    fn chess960_index_not_start() {
+        let mut position = Position::chess960(518);
+
+        position.do_move(Move::new(Square::G1, Square::F3));
+        assert_eq!(None, position.chess960_index());
+
+        position.do_move(Move::new(Square::G8, Square::F6));
+        position.do_move(Move::new(Square::F3, Square::G1));
+        position.do_move(Move::new(Square::F6, Square::G8));
+        assert_eq!(Some(518), position.chess960_index());
+
+        // the pieces are where they started, but castling rights are missing
+        assert_eq!(None, Position::from_fen(Ruleset::Standard,
+            b"rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w Kkq - 0 1").chess960_index());
+
+        assert_eq!(None, Position::from_fen(Ruleset::Standard,
+            b"rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1").chess960_index());
+    }
+
+    #[test]
+    fn double_chess960() {
+        let position = Position::double_chess960(0, 959);
+
+        assert_eq!("rkrnnqbb/pppppppp/8/8/8/8/PPPPPPPP/BBQNNRKR w HFca - 0 1", position.to_fen());
+        assert_eq!(Some((0, 959)), position.double_chess960_indexes());
+        assert_eq!(None,           position.chess960_index());
+
+        assert_eq!(Some((518, 518)), Position::chess960(518).double_chess960_indexes());
+    }
+
+    #[test]
+    fn double_chess960_perft() {
+        let mut position = Position::double_chess960(0, 959);
+
+        assert_eq!(400, position.perft(2));
+    }
+}
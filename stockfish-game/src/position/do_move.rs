@@ -0,0 +1,753 @@
+use crate::prelude::*;
+use stockfish_core::prelude::*;
+
+#[allow(clippy::multiple_inherent_impl)]
+impl Position {
+    /// Makes a move on the board, saving enough information that it can later
+    /// be reversed with [`Position::undo_move`].
+    ///
+    /// The move *must* be legal in this position. Passing any other move may
+    /// leave the position in a corrupted state.
+    pub fn do_move(&mut self, mv: Move) {
+        let us          = self.turn;
+        let them        = !us;
+        let origin      = mv.origin();
+        let destination = mv.destination();
+        let piece       = match mv.move_type() {
+            MoveType::Drop => Some(us | mv.dropped()),
+            _              => self.board[origin],
+        };
+
+        debug_assert_eq!(Some(us), piece.map(Piece::color),
+            "the moving piece must belong to the side to move");
+
+        self.history.push(self.state);
+        self.ply += 1;
+
+        self.state.keys.toggle_en_passant(self.state.en_passant);
+
+        self.state.halfmoves         = self.state.halfmoves.saturating_add(1);
+        self.state.plies_from_null   = self.state.plies_from_null.saturating_add(1);
+        self.state.en_passant        = None;
+        self.state.captured          = None;
+        self.state.captured_promoted = false;
+        self.state.blast             = Bitboard::EMPTY;
+        self.state.exploded          = [None; 9];
+
+        // castling is encoded as the king capturing its own rook, so it must
+        // be handled before anything else gets a chance to look at the
+        // destination square
+        match mv.move_type() {
+            MoveType::Castling => self.do_castling(us, origin, destination),
+
+            MoveType::Drop => {
+                self.take_from_pocket(us | mv.dropped());
+                self.emplace(us | mv.dropped(), destination);
+            },
+
+            move_type => {
+                let captured = if move_type == MoveType::EnPassant {
+                    destination.wrapping_sub(us.direction())
+                } else {
+                    destination
+                };
+
+                if let Some(piece) = self.remove(captured) {
+                    debug_assert_eq!(them, piece.color(), "only enemy pieces may be captured");
+                    debug_assert!(piece.token() != Token::King || self.ruleset == Ruleset::Antichess,
+                        "kings may only be captured in antichess");
+
+                    self.state.captured  = Some(piece);
+                    self.state.halfmoves = 0;
+
+                    if self.ruleset == Ruleset::Crazyhouse {
+                        self.pocket_captured(piece, captured);
+                    }
+                }
+
+                self.move_piece(origin, destination);
+            },
+        }
+
+        // moving a king or rook from its starting square, or capturing a rook
+        // on its starting square, forfeits the corresponding castling rights
+        self.forfeit_castling(self.castling_by_square[origin] | self.castling_by_square[destination]);
+
+        // dropping a pawn is every bit as irreversible as pushing one
+        if piece.map(Piece::token) == Some(Token::Pawn) {
+            self.state.halfmoves = 0;
+
+            // following X-FEN, only record the en passant square if an enemy
+            // pawn is actually in position to capture onto it; pawns in the
+            // horde advancing two squares from the first rank can't be
+            // captured en passant
+            if origin.distance_ranks(destination) == 2
+                && origin.from_perspective(us).rank() == Rank::_2
+            {
+                let en_passant = origin.wrapping_add(us.direction());
+
+                if (us | Token::Pawn).attacks(en_passant, self.bb_all)
+                    .overlaps(self.bitboard_for_token(them | Token::Pawn))
+                {
+                    self.state.en_passant = Some(en_passant);
+                    self.state.keys.toggle_en_passant(self.state.en_passant);
+                }
+            }
+
+            if mv.move_type() == MoveType::Promotion {
+                let _ = self.remove(destination);
+                self.emplace(us | mv.promotion(), destination);
+
+                if self.ruleset == Ruleset::Crazyhouse {
+                    self.promoted |= destination;
+                }
+            }
+        }
+
+        // the explosion has to wait until after any promotion, since the
+        // promoted piece is the one that gets blown up
+        if self.ruleset == Ruleset::Atomic && self.state.captured.is_some() {
+            self.explode(destination);
+        }
+
+        self.turn = them;
+        self.state.keys.toggle_side();
+        self.update_check_info();
+
+        // the game is over once the third check is given, so there's never a
+        // fourth to count
+        if self.ruleset == Ruleset::ThreeCheck
+            && self.state.checkers.is_any()
+            && usize::from(self.state.checks[us]) < Ruleset::MAX_CHECKS
+        {
+            self.state.keys.toggle_checks(us, self.state.checks[us]);
+            self.state.checks[us] += 1;
+        }
+
+        self.update_repetition();
+
+        debug_assert_eq!(Ok(()), self.validate_invariants());
+    }
+
+    /// Reverses a move previously made with [`Position::do_move`], restoring
+    /// the position to exactly the state it was in beforehand.
+    ///
+    /// The move *must* be the last one made on this position.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no move has been made on this position.
+    #[allow(clippy::expect_used)]
+    pub fn undo_move(&mut self, mv: Move) {
+        let us          = !self.turn;
+        let origin      = mv.origin();
+        let destination = mv.destination();
+
+        // put back everything caught in the explosion first, which returns
+        // the capturing piece to the destination square so the rest of the
+        // move can be undone as usual
+        if self.state.blast.is_any() {
+            self.unexplode();
+        }
+
+        match mv.move_type() {
+            MoveType::Castling => self.undo_castling(us, origin, destination),
+
+            MoveType::Drop => {
+                let _ = self.remove(destination);
+                self.add_to_pocket(us | mv.dropped());
+            },
+
+            move_type => {
+                if move_type == MoveType::Promotion {
+                    let _ = self.remove(destination);
+                    self.emplace(us | Token::Pawn, destination);
+                    self.promoted &= !Bitboard::from(destination);
+                }
+
+                self.move_piece(destination, origin);
+
+                if let Some(piece) = self.state.captured {
+                    let captured = if move_type == MoveType::EnPassant {
+                        destination.wrapping_sub(us.direction())
+                    } else {
+                        destination
+                    };
+
+                    self.emplace(piece, captured);
+
+                    if self.ruleset == Ruleset::Crazyhouse {
+                        self.unpocket_captured(piece, captured);
+                    }
+                }
+            },
+        }
+
+        self.state = self.history.pop().expect("undo_move without a matching do_move");
+        self.ply  -= 1;
+        self.turn  = us;
+
+        debug_assert_eq!(Ok(()), self.validate_invariants());
+    }
+
+    /// Passes the turn to the opponent without moving a piece, saving enough
+    /// information that it can later be reversed with
+    /// [`Position::undo_null_move`].
+    ///
+    /// Null moves aren't legal chess, but they're used by the search to prove
+    /// that a position is so good that even skipping a turn wouldn't lose it.
+    /// Since passing while in check would leave the king en prise, the null
+    /// move is rejected in that case: [`false`] is returned and the position
+    /// is left untouched.
+    #[must_use]
+    pub fn do_null_move(&mut self) -> bool {
+        if self.state.checkers.is_any() {
+            return false;
+        }
+
+        self.history.push(self.state);
+        self.ply += 1;
+
+        self.state.keys.toggle_en_passant(self.state.en_passant);
+
+        self.state.halfmoves         = self.state.halfmoves.saturating_add(1);
+        self.state.plies_from_null   = 0;
+        self.state.en_passant        = None;
+        self.state.captured          = None;
+        self.state.captured_promoted = false;
+        self.state.blast             = Bitboard::EMPTY;
+        self.state.exploded          = [None; 9];
+        self.state.repetition        = 0;
+
+        self.turn = !self.turn;
+        self.state.keys.toggle_side();
+        self.update_check_info();
+
+        debug_assert_eq!(Ok(()), self.validate_invariants());
+
+        true
+    }
+
+    /// Reverses a null move previously made with [`Position::do_null_move`].
+    ///
+    /// The null move *must* be the last move made on this position.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no move has been made on this position.
+    #[allow(clippy::expect_used)]
+    pub fn undo_null_move(&mut self) {
+        debug_assert!(self.state.plies_from_null == 0, "the last move must be a null move");
+
+        self.state = self.history.pop().expect("undo_null_move without a matching do_null_move");
+        self.ply  -= 1;
+        self.turn  = !self.turn;
+
+        debug_assert_eq!(Ok(()), self.validate_invariants());
+    }
+
+    /// Moves the piece on `origin` to the (empty) `destination` square.
+    fn move_piece(&mut self, origin: Square, destination: Square) {
+        debug_assert!(self.board[destination].is_none(), "destination must be empty");
+
+        if let Some(piece) = self.remove(origin) {
+            self.emplace(piece, destination);
+        }
+
+        // a promoted piece stays promoted wherever it goes
+        if self.promoted.contains(origin) {
+            self.promoted ^= origin;
+            self.promoted |= destination;
+        }
+    }
+
+    /// Removes the castling rights in `lost`, if they haven't been already.
+    fn forfeit_castling(&mut self, lost: CastlingRights) {
+        if self.state.castling_rights.intersects(lost) {
+            self.state.keys.toggle_castling(self.state.castling_rights);
+            self.state.castling_rights &= !lost;
+            self.state.keys.toggle_castling(self.state.castling_rights);
+        }
+    }
+
+    /// Blows up the piece that just captured on `square`, along with every
+    /// piece other than a pawn on the surrounding squares, recording them so
+    /// that the explosion can be reversed by [`Position::unexplode`].
+    ///
+    /// Kings and rooks caught in the blast take their castling rights with
+    /// them.
+    fn explode(&mut self, square: Square) {
+        let blast = self.blast(square) & self.bb_all;
+        let mut lost = CastlingRights::NONE;
+
+        self.state.blast = blast;
+
+        for (i, square) in blast.into_iter().enumerate() {
+            self.state.exploded[i] = self.remove(square);
+            lost                  |= self.castling_by_square[square];
+        }
+
+        self.forfeit_castling(lost);
+    }
+
+    /// Returns every piece destroyed by the last move's explosion to the
+    /// square it was on.
+    fn unexplode(&mut self) {
+        let blast    = self.state.blast;
+        let exploded = self.state.exploded;
+
+        for (square, piece) in blast.into_iter().zip(exploded) {
+            if let Some(piece) = piece {
+                self.emplace(piece, square);
+            }
+        }
+    }
+
+    /// Puts the `piece` just captured on `square` into the capturing side's
+    /// pocket, reverting it to a pawn if it had been promoted.
+    fn pocket_captured(&mut self, piece: Piece, square: Square) {
+        let promoted = self.promoted.contains(square);
+        let token    = if promoted { Token::Pawn } else { piece.token() };
+
+        self.promoted                &= !Bitboard::from(square);
+        self.state.captured_promoted  = promoted;
+
+        self.add_to_pocket(!piece.color() | token);
+    }
+
+    /// Takes the `piece` being returned to `square` back out of the capturing
+    /// side's pocket, undoing [`Position::pocket_captured`].
+    fn unpocket_captured(&mut self, piece: Piece, square: Square) {
+        let promoted = self.state.captured_promoted;
+        let token    = if promoted { Token::Pawn } else { piece.token() };
+
+        if promoted {
+            self.promoted |= square;
+        }
+
+        self.take_from_pocket(!piece.color() | token);
+    }
+
+    /// Moves the king on `king` and the rook on `rook` to their destination
+    /// squares after castling.
+    fn do_castling(&mut self, color: Color, king: Square, rook: Square) {
+        let (king_destination, rook_destination) = Self::castling_destinations(color, king, rook);
+
+        // in Chess960 the king and rook may end up on each other's starting
+        // squares, so both need to be lifted off the board before either can
+        // be put back down
+        let _ = self.remove(king);
+        let _ = self.remove(rook);
+
+        self.emplace(color | Token::King, king_destination);
+        self.emplace(color | Token::Rook, rook_destination);
+    }
+
+    /// Returns the king and rook on `king` and `rook` back to their original
+    /// squares from before castling.
+    fn undo_castling(&mut self, color: Color, king: Square, rook: Square) {
+        let (king_destination, rook_destination) = Self::castling_destinations(color, king, rook);
+
+        let _ = self.remove(king_destination);
+        let _ = self.remove(rook_destination);
+
+        self.emplace(color | Token::King, king);
+        self.emplace(color | Token::Rook, rook);
+    }
+
+    /// Returns the squares the king and rook end up on after castling.
+    pub(crate) fn castling_destinations(color: Color, king: Square, rook: Square) -> (Square, Square) {
+        let side = if king.file() < rook.file() {
+            CastlingSide::King
+        } else {
+            CastlingSide::Queen
+        };
+
+        let variety = CastlingVariety::new(color, side);
+
+        (variety.king_destination(), variety.rook_destination())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Makes and unmakes every legal move in the position, ensuring that
+    /// the position is restored afterwards.
+    fn assert_reversible(ruleset: Ruleset, fen: &str) {
+        let mut position = Position::from_fen(ruleset, fen.as_bytes());
+        let original     = position.clone();
+
+        for mv in &position.clone().legal_moves() {
+            position.do_move(*mv);
+
+            assert_eq!(!original.turn(),   position.turn());
+            assert_eq!(original.ply() + 1, position.ply());
+
+            position.undo_move(*mv);
+
+            assert_eq!(original, position, "{mv:?} was not reversible");
+        }
+    }
+
+    #[test]
+    fn do_move_reversible() {
+        assert_reversible(Ruleset::Standard,
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+        assert_reversible(Ruleset::Standard,
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1");
+        assert_reversible(Ruleset::Standard,
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/Pp2P3/2N2Q1p/1PPBBPPP/R3K2R b KQkq a3 0 1");
+        assert_reversible(Ruleset::Standard,
+            "n1n5/PPPk4/8/8/8/8/4Kppp/5N1N b - - 0 1");
+        assert_reversible(Ruleset::Chess960,
+            "1r2k1r1/8/8/8/8/8/8/1R2K1R1 w GBgb - 0 1");
+        assert_reversible(Ruleset::Chess960,
+            "4k3/8/8/8/8/8/8/5KR1 w G - 0 1");
+        assert_reversible(Ruleset::Crazyhouse,
+            "r1bqk2r/pppp1ppp/2n1p3/4P3/1b1Pn3/2NB1N2/PPP2PPP/R1BQK2R[Pn] b KQkq - 0 1");
+        assert_reversible(Ruleset::Crazyhouse,
+            "4k3/1Q~6/8/8/4b3/8/Kpp5/8[] b - - 0 1");
+        assert_reversible(Ruleset::Atomic,
+            "rnb1kbnr/pppp1ppp/8/4p3/4P2q/8/PPPPKPPP/RNBQ1BNR b kq - 0 1");
+        assert_reversible(Ruleset::Atomic,
+            "rn2kb1r/1pp1p2p/p2q1pp1/3P4/2P3b1/4PN2/PP3PPP/R2QKB1R b KQkq - 0 1");
+        assert_reversible(Ruleset::Antichess,
+            "rnbqkbnr/pppp1ppp/8/4p3/3P4/8/PPP1PPPP/RNBQKBNR w - - 0 2");
+        assert_reversible(Ruleset::Antichess,
+            "1n2k3/P1P5/8/8/8/8/5K2/8 w - - 0 1");
+        assert_reversible(Ruleset::ThreeCheck,
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1 +1+2");
+        assert_reversible(Ruleset::KingOfTheHill,
+            "8/8/8/8/8/2K5/8/k7 w - - 0 1");
+    }
+
+    #[test]
+    #[should_panic(expected = "without a matching do_move")]
+    fn undo_move_without_do_move() {
+        let mut position = Position::from_fen(Ruleset::Standard,
+            b"4k3/8/8/8/8/8/8/R3K3 w - - 0 1");
+
+        position.undo_move(Move::new(Square::A2, Square::A1));
+    }
+
+    #[test]
+    fn do_move_horde_en_passant() {
+        let mut position = Position::from_fen(Ruleset::Horde,
+            b"4k3/8/8/8/1p6/8/8/P7 w - - 0 1");
+
+        // leaping from the first rank can't be captured en passant, even with
+        // a pawn in position to do so
+        position.do_move(Move::new(Square::A1, Square::A3));
+
+        assert_eq!("4k3/8/8/8/1p6/P7/8/8 b - - 0 1", position.to_fen());
+
+        let mut position = Position::from_fen(Ruleset::Horde,
+            b"4k3/8/8/8/1p6/8/P7/8 w - - 0 1");
+
+        position.do_move(Move::new(Square::A2, Square::A4));
+
+        assert_eq!("4k3/8/8/8/Pp6/8/8/8 b - a3 0 1", position.to_fen());
+    }
+
+    #[test]
+    fn do_move_three_check() {
+        let mut position = Position::from_fen(Ruleset::ThreeCheck,
+            b"4k3/8/8/8/8/8/8/4K2R w - - 0 1 +1+0");
+
+        let original = position.clone();
+
+        position.do_move(Move::new(Square::H1, Square::H8));
+
+        // the check is counted, and is part of the position's key
+        assert_eq!(2, position.checks_given(Color::White));
+        assert_eq!("4k2R/8/8/8/8/8/8/4K3 b - - 1 1 +2+0", position.to_fen());
+        assert_eq!(Position::from_fen(Ruleset::ThreeCheck,
+            b"4k2R/8/8/8/8/8/8/4K3 b - - 1 1 +2+0").key(), position.key());
+        assert_ne!(Position::from_fen(Ruleset::ThreeCheck,
+            b"4k2R/8/8/8/8/8/8/4K3 b - - 1 1 +1+0").key(), position.key());
+
+        position.undo_move(Move::new(Square::H1, Square::H8));
+
+        assert_eq!(original, position);
+    }
+
+    #[test]
+    fn do_move_antichess_king() {
+        let mut position = Position::from_fen(Ruleset::Antichess,
+            b"4k3/3Q4/8/8/8/8/6pP/8 b - - 0 1");
+
+        // kings capture and are captured like any other piece, and a pawn
+        // may promote to one
+        position.do_move(Move::new(Square::E8, Square::D7));
+        position.do_move(Move::new(Square::H2, Square::H3));
+        position.do_move(Move::new_promote_king(Square::G2, Square::G1));
+
+        assert_eq!(Square::D7 | Square::G1,    position.bitboard_for_piece(Token::King));
+        assert_eq!(Bitboard::from(Square::H3), position.bitboard_for_color(Color::White));
+    }
+
+    #[test]
+    fn do_move_crazyhouse_capture() {
+        let mut position = Position::from_fen(Ruleset::Crazyhouse,
+            b"4k3/1Q~6/8/8/4b3/8/Kpp5/8[] b - - 0 1");
+
+        // the captured queen was once a pawn, so it's demoted on capture
+        position.do_move(Move::new(Square::E4, Square::B7));
+
+        assert_eq!(1, position.pocket(Piece::BlackPawn));
+        assert_eq!(0, position.pocket(Piece::BlackQueen));
+        assert_eq!(Bitboard::EMPTY, position.promoted());
+
+        position.do_move(Move::new(Square::A2, Square::A3));
+        position.do_move(Move::new_drop(Token::Pawn, Square::B4));
+
+        assert_eq!(0,                      position.pocket(Piece::BlackPawn));
+        assert_eq!(Some(Piece::BlackPawn), position[Square::B4]);
+        assert!(position.checkers().contains(Square::B4));
+    }
+
+    #[test]
+    fn do_move_crazyhouse_promotion() {
+        let mut position = Position::from_fen(Ruleset::Crazyhouse,
+            b"4k3/1Q~6/8/8/4b3/8/Kpp5/8[] b - - 0 1");
+
+        position.do_move(Move::new_promote_knight(Square::B2, Square::B1));
+        assert_eq!(Square::B1 | Square::B7, position.promoted());
+
+        position.undo_move(Move::new_promote_knight(Square::B2, Square::B1));
+        assert_eq!(Bitboard::from(Square::B7), position.promoted());
+    }
+
+    #[test]
+    fn do_move_normal() {
+        let mut position = Position::from_fen(Ruleset::Standard,
+            b"rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+
+        position.do_move(Move::new(Square::G1, Square::F3));
+
+        assert_eq!(None,                     position[Square::G1]);
+        assert_eq!(Some(Piece::WhiteKnight), position[Square::F3]);
+        assert_eq!(Color::Black,             position.turn());
+        assert_eq!(1,                        position.halfmoves());
+        assert_eq!(CastlingRights::ANY,      position.castling_rights());
+    }
+
+    #[test]
+    fn do_move_double_push() {
+        let mut position = Position::from_fen(Ruleset::Standard,
+            b"4k3/8/8/8/3p4/8/4P3/4K3 w - - 7 1");
+
+        position.do_move(Move::new(Square::E2, Square::E4));
+
+        assert_eq!(Some(Square::E3), position.en_passant());
+        assert_eq!(0,                position.halfmoves());
+
+        // without an enemy pawn to capture it, no en passant square is set
+        let mut position = Position::from_fen(Ruleset::Standard,
+            b"4k3/8/8/8/8/8/4P3/4K3 w - - 0 1");
+
+        position.do_move(Move::new(Square::E2, Square::E4));
+
+        assert_eq!(None, position.en_passant());
+    }
+
+    #[test]
+    fn do_move_en_passant() {
+        let mut position = Position::from_fen(Ruleset::Standard,
+            b"4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1");
+
+        position.do_move(Move::new_en_passant(Square::E5, Square::D6));
+
+        assert_eq!(None,                   position[Square::D5]);
+        assert_eq!(None,                   position[Square::E5]);
+        assert_eq!(Some(Piece::WhitePawn), position[Square::D6]);
+        assert_eq!(Some(Piece::BlackPawn), position.captured());
+    }
+
+    #[test]
+    fn do_move_promotion() {
+        let mut position = Position::from_fen(Ruleset::Standard,
+            b"1r6/P2k4/8/8/8/8/8/4K3 w - - 3 1");
+
+        position.do_move(Move::new_promote_knight(Square::A7, Square::B8));
+
+        assert_eq!(Some(Piece::WhiteKnight),   position[Square::B8]);
+        assert_eq!(Some(Piece::BlackRook),     position.captured());
+        assert_eq!(0,                          position.halfmoves());
+        assert_eq!(Bitboard::from(Square::B8), position.checkers());
+    }
+
+    #[test]
+    fn do_move_castling() {
+        let mut position = Position::from_fen(Ruleset::Standard,
+            b"r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1");
+
+        position.do_move(Move::new_castling(Square::E1, Square::A1));
+
+        assert_eq!(Some(Piece::WhiteKing), position[Square::C1]);
+        assert_eq!(Some(Piece::WhiteRook), position[Square::D1]);
+        assert_eq!(None,                   position[Square::A1]);
+        assert_eq!(None,                   position[Square::E1]);
+        assert_eq!(CastlingRights::BLACK,  position.castling_rights());
+    }
+
+    #[test]
+    fn do_move_castling_chess960_swap() {
+        let mut position = Position::from_fen(Ruleset::Chess960,
+            b"4k3/8/8/8/8/8/8/5KR1 w G - 0 1");
+
+        // the king and rook trade places
+        position.do_move(Move::new_castling(Square::F1, Square::G1));
+
+        assert_eq!(Some(Piece::WhiteKing), position[Square::G1]);
+        assert_eq!(Some(Piece::WhiteRook), position[Square::F1]);
+        assert_eq!(CastlingRights::NONE,   position.castling_rights());
+
+        position.undo_move(Move::new_castling(Square::F1, Square::G1));
+
+        assert_eq!(Some(Piece::WhiteKing),   position[Square::F1]);
+        assert_eq!(Some(Piece::WhiteRook),   position[Square::G1]);
+        assert_eq!(CastlingRights::WHITE_OO, position.castling_rights());
+    }
+
+    #[test]
+    fn do_move_rook_capture_loses_castling() {
+        let mut position = Position::from_fen(Ruleset::Standard,
+            b"r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1");
+
+        position.do_move(Move::new(Square::A1, Square::A8));
+
+        assert_eq!(CastlingRights::WHITE_OO | CastlingRights::BLACK_OO,
+            position.castling_rights());
+    }
+
+    #[test]
+    fn do_move_keys_transpose() {
+        let start = Position::from_fen(Ruleset::Standard,
+            b"rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+
+        let mut p1 = start.clone();
+        let mut p2 = start.clone();
+
+        for mv in [
+            Move::new(Square::G1, Square::F3),
+            Move::new(Square::B8, Square::C6),
+            Move::new(Square::B1, Square::C3),
+            Move::new(Square::G8, Square::F6),
+        ] {
+            p1.do_move(mv);
+        }
+
+        for mv in [
+            Move::new(Square::B1, Square::C3),
+            Move::new(Square::G8, Square::F6),
+            Move::new(Square::G1, Square::F3),
+            Move::new(Square::B8, Square::C6),
+        ] {
+            p2.do_move(mv);
+        }
+
+        assert_eq!(p1.key(),          p2.key());
+        assert_eq!(p1.pawn_key(),     start.pawn_key());
+        assert_eq!(p1.material_key(), start.material_key());
+        assert_ne!(p1.key(),          start.key());
+
+        assert_ne!(p1.non_pawn_key(Color::White), start.non_pawn_key(Color::White));
+        assert_ne!(p1.non_pawn_key(Color::Black), start.non_pawn_key(Color::Black));
+    }
+
+    #[test]
+    fn do_move_keys_side_to_move() {
+        let mut position = Position::from_fen(Ruleset::Standard,
+            b"4k3/8/8/8/8/8/8/4K3 w - - 0 1");
+
+        let white = position.key();
+
+        position.do_move(Move::new(Square::E1, Square::E2));
+        position.do_move(Move::new(Square::E8, Square::E7));
+        position.do_move(Move::new(Square::E2, Square::E1));
+
+        assert_eq!(Position::from_fen(Ruleset::Standard,
+            b"8/4k3/8/8/8/8/8/4K3 b - - 0 1").key(), position.key());
+
+        position.do_move(Move::new(Square::E7, Square::E8));
+
+        assert_eq!(white, position.key());
+    }
+
+    #[test]
+    fn do_move_keys_material() {
+        let mut position = Position::from_fen(Ruleset::Standard,
+            b"4k3/8/8/8/8/8/3r4/3QK3 w - - 0 1");
+
+        position.do_move(Move::new(Square::D1, Square::D2));
+
+        assert_eq!(Position::from_fen(Ruleset::Standard,
+            b"4k3/8/8/8/8/8/8/3QK3 w - - 0 1").material_key(), position.material_key());
+    }
+
+    #[test]
+    fn do_move_keys_en_passant() {
+        let mut position = Position::from_fen(Ruleset::Standard,
+            b"4k3/8/8/8/3p4/8/4P3/4K3 w - - 0 1");
+
+        position.do_move(Move::new(Square::E2, Square::E4));
+
+        let with    = Position::from_fen(Ruleset::Standard, b"4k3/8/8/8/3pP3/8/8/4K3 b - e3 0 1");
+        let without = Position::from_fen(Ruleset::Standard, b"4k3/8/8/8/3pP3/8/8/4K3 b - - 0 1");
+
+        assert_eq!(with.key(),    position.key());
+        assert_ne!(without.key(), position.key());
+    }
+
+    #[test]
+    fn do_null_move() {
+        let mut position = Position::from_fen(Ruleset::Standard,
+            b"rnbqkbnr/ppp1pppp/8/8/3pP3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 3");
+
+        let original = position.clone();
+
+        assert!(position.do_null_move());
+
+        assert_eq!(Color::White, position.turn());
+        assert_eq!(None,         position.en_passant());
+        assert_eq!(1,            position.halfmoves());
+        assert_eq!(original.bitboard(), position.bitboard());
+        assert_ne!(original.key(),      position.key());
+        assert_eq!(position.compute_keys(), position.state.keys);
+
+        position.undo_null_move();
+
+        assert_eq!(original, position);
+    }
+
+    #[test]
+    fn do_null_move_in_check() {
+        let mut position = Position::from_fen(Ruleset::Standard,
+            b"4k3/8/8/8/8/8/8/4R1K1 b - - 0 1");
+
+        let original = position.clone();
+
+        assert!(!position.do_null_move());
+        assert_eq!(original, position);
+    }
+
+    #[test]
+    fn do_null_move_breaks_repetition() {
+        let mut position = Position::from_fen(Ruleset::Standard,
+            b"rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+
+        // shuffling a knight back and forth around a pair of null moves
+        // reaches the same position, but doesn't count as a repetition since
+        // a null move isn't a real move
+        position.do_move(Move::new(Square::G1, Square::F3));
+        assert!(position.do_null_move());
+        position.do_move(Move::new(Square::F3, Square::G1));
+        assert!(position.do_null_move());
+        position.do_move(Move::new(Square::G1, Square::F3));
+
+        assert!(!position.has_repeated(1));
+    }
+}
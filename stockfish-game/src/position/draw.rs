@@ -0,0 +1,462 @@
+use crate::prelude::*;
+use stockfish_core::prelude::*;
+
+/// The number of halfmoves without a capture or pawn advance after which
+/// either player may claim a draw.
+pub(super) const FIFTY_MOVES: u8 = 100;
+
+#[allow(clippy::multiple_inherent_impl)]
+impl Position {
+    /// Returns [`true`] if the position is drawn by the fifty-move rule or by
+    /// repetition, given the number of plies searched since the root of the
+    /// search.
+    ///
+    /// A position counts as a repetition if it occurred once before at some
+    /// point after the root (since the side that repeated it could simply keep
+    /// doing so), or twice before anywhere in the game.
+    #[must_use]
+    pub fn is_draw(&self, ply_from_root: u16) -> bool {
+        // the fifty-move rule doesn't apply if the move that reached it also
+        // delivered checkmate
+        if self.state.halfmoves >= FIFTY_MOVES
+            && (self.state.checkers.is_empty() || !self.legal_moves().is_empty())
+        {
+            return true;
+        }
+
+        self.state.repetition != 0
+            && i32::from(self.state.repetition) < i32::from(ply_from_root)
+    }
+
+    /// Returns [`true`] if the current position has occurred before, at least
+    /// `count` times, since the last irreversible move.
+    #[must_use]
+    pub fn has_repeated(&self, count: u8) -> bool {
+        self.repetitions() >= count
+    }
+
+    /// Returns [`true`] if neither side has enough material left on the board
+    /// to possibly deliver checkmate.
+    ///
+    /// This is the case when there are no pawns, rooks, or queens on the
+    /// board, and either there is at most a single minor piece or every minor
+    /// piece is a bishop and they're all on squares of the same color.
+    ///
+    /// In crazyhouse any captured piece may come back to deliver mate, so
+    /// only two bare kings with nothing in either pocket are insufficient. In
+    /// atomic chess, neither side must be able to blow up the enemy king
+    /// either. In antichess, the game can only be drawn this way when nothing
+    /// but bishops remain and each side's bishops are confined to squares of
+    /// a different color than the other's, so that neither can ever capture
+    /// or be captured. In three-check any piece at all is enough to give
+    /// check, and in king of the hill a bare king can always try for the
+    /// center. Neither is enough for a draw in horde, where black wins by
+    /// capturing everything, or in racing kings, where the kings race rather
+    /// than mate.
+    #[must_use]
+    pub fn has_insufficient_material(&self) -> bool {
+        if self.ruleset == Ruleset::ThreeCheck {
+            return self.bb_all == self.bb_by_piece[Token::King];
+        }
+
+        if matches!(self.ruleset, Ruleset::KingOfTheHill | Ruleset::Horde | Ruleset::RacingKings) {
+            return false;
+        }
+
+        if self.ruleset == Ruleset::Crazyhouse {
+            return self.bb_all == self.bb_by_piece[Token::King]
+                && self.pockets.iter().all(|&count| count == 0);
+        }
+
+        if self.ruleset == Ruleset::Atomic {
+            return Color::iter().all(|color| self.has_insufficient_atomic_material(color));
+        }
+
+        if self.ruleset == Ruleset::Antichess {
+            return self.has_insufficient_antichess_material();
+        }
+
+        let count = |token: Token| {
+            self.count_by_token[Color::White | token] +
+            self.count_by_token[Color::Black | token]
+        };
+
+        if count(Token::Pawn) + count(Token::Rook) + count(Token::Queen) > 0 {
+            return false;
+        }
+
+        let knights = count(Token::Knight);
+        let bishops = self.bitboard_for_piece(Token::Bishop);
+
+        if knights + count(Token::Bishop) <= 1 {
+            return true;
+        }
+
+        knights == 0 && (
+            !bishops.overlaps(Bitboard::DARK_SQUARES) ||
+            !bishops.overlaps(Bitboard::LIGHT_SQUARES)
+        )
+    }
+
+    /// Returns [`true`] if `color` has no way left to win a game of atomic
+    /// chess, either by checkmate or by blowing up the enemy king.
+    fn has_insufficient_atomic_material(&self, color: Color) -> bool {
+        let kings   = self.bb_by_piece[Token::King];
+        let ours    = self.bb_by_color[color];
+        let theirs  = self.bb_by_color[!color];
+        let bishops = self.bb_by_piece[Token::Bishop];
+        let knights = self.bb_by_piece[Token::Knight];
+
+        // once the enemy king is gone, there's nothing left to win
+        if !theirs.overlaps(kings) {
+            return false;
+        }
+
+        // a bare king can't capture anything, so it can't win
+        if (ours & !kings).is_empty() {
+            return true;
+        }
+
+        // as long as the enemy has pieces besides their king, one of them
+        // might be captured next to it, unless the only pieces are bishops
+        // that can never meet
+        if (theirs & !kings).is_any() {
+            if self.bb_all != kings | bishops {
+                return false;
+            }
+
+            let meet = |squares: Bitboard| {
+                (bishops & ours).overlaps(squares) && (bishops & theirs).overlaps(squares)
+            };
+
+            return !meet(Bitboard::DARK_SQUARES) && !meet(Bitboard::LIGHT_SQUARES);
+        }
+
+        // against a bare king, a queen or pawn (soon to be a queen) is always
+        // enough, but a single minor piece or rook, or two knights, aren't
+        if (self.bb_by_piece[Token::Queen] | self.bb_by_piece[Token::Pawn]).is_any() {
+            return false;
+        }
+
+        if (knights | bishops | self.bb_by_piece[Token::Rook]).count() == 1 {
+            return true;
+        }
+
+        self.bb_all == kings | knights && knights.count() <= 2
+    }
+
+    /// Returns [`true`] if neither side can ever capture a piece of the other
+    /// in antichess, and so neither can give away every piece.
+    fn has_insufficient_antichess_material(&self) -> bool {
+        let bishops = self.bb_by_piece[Token::Bishop];
+        let white   = self.bb_by_color[Color::White];
+        let black   = self.bb_by_color[Color::Black];
+
+        // a side without any pieces has already won
+        if white.is_empty() || black.is_empty() || self.bb_all != bishops {
+            return false;
+        }
+
+        let confined = |ours: Bitboard, theirs: Bitboard| {
+            !ours.overlaps(Bitboard::DARK_SQUARES) && !theirs.overlaps(Bitboard::LIGHT_SQUARES)
+        };
+
+        confined(white, black) || confined(black, white)
+    }
+
+    /// Returns the number of times the current position has occurred before
+    /// since the last irreversible move.
+    pub(crate) fn repetitions(&self) -> u8 {
+        let mut count      = 0;
+        let mut repetition = self.state.repetition;
+        let mut index      = self.history.len();
+
+        // each state points back to the previous occurrence of the same
+        // position, if any, so we can simply follow the chain
+        while repetition != 0 {
+            count += 1;
+
+            let distance = usize::from(repetition.unsigned_abs());
+
+            if repetition < 0 && distance <= index {
+                index     -= distance;
+                repetition = self.history.get(index).map_or(0, |state| state.repetition);
+            } else {
+                break;
+            }
+        }
+
+        count
+    }
+
+    /// Records whether the current position is a repetition of one reached
+    /// earlier, by walking back through the history until the last
+    /// irreversible move or null move.
+    pub(crate) fn update_repetition(&mut self) {
+        let end = usize::from(self.state.halfmoves)
+            .min(usize::from(self.state.plies_from_null))
+            .min(self.history.len());
+        let key = self.state.keys.position;
+
+        self.state.repetition = 0;
+
+        // positions can only repeat with the same side to move, and it takes
+        // at least four plies to get back to where you started
+        for distance in (4..=end).step_by(2) {
+            let previous = &self.history[self.history.len() - distance];
+
+            if previous.keys.position == key {
+                #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+                let distance = distance as i16;
+
+                self.state.repetition = if previous.repetition == 0 { distance } else { -distance };
+                break;
+            }
+        }
+    }
+}
+
+/// Knight moves that return the starting position to itself after four
+/// plies, for testing repetitions.
+#[cfg(test)]
+pub(super) const SHUFFLE: [Move; 4] = [
+    Move::new(Square::G1, Square::F3),
+    Move::new(Square::G8, Square::F6),
+    Move::new(Square::F3, Square::G1),
+    Move::new(Square::F6, Square::G8),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn is_draw_repetition() {
+        let mut position = Position::from_fen(Ruleset::Standard,
+            b"rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+
+        for mv in SHUFFLE {
+            assert!(!position.is_draw(0));
+            position.do_move(mv);
+        }
+
+        // a single repetition only counts if it happened after the root
+        assert!(!position.is_draw(0));
+        assert!(!position.is_draw(4));
+        assert!( position.is_draw(5));
+        assert!( position.has_repeated(1));
+        assert!(!position.has_repeated(2));
+
+        for mv in SHUFFLE {
+            position.do_move(mv);
+        }
+
+        // a second repetition always counts
+        assert!(position.is_draw(0));
+        assert!(position.has_repeated(2));
+        assert!(!position.has_repeated(3));
+    }
+
+    #[test]
+    fn is_draw_repetition_reset_by_irreversible_move() {
+        let mut position = Position::from_fen(Ruleset::Standard,
+            b"rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+
+        for mv in SHUFFLE {
+            position.do_move(mv);
+        }
+
+        position.do_move(Move::new(Square::E2, Square::E4));
+        position.do_move(Move::new(Square::E7, Square::E5));
+
+        for mv in SHUFFLE {
+            position.do_move(mv);
+        }
+
+        assert!(position.has_repeated(1));
+        assert!(!position.has_repeated(2));
+    }
+
+    #[test]
+    fn is_draw_undo_restores_repetition() {
+        let mut position = Position::from_fen(Ruleset::Standard,
+            b"rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+
+        for mv in SHUFFLE {
+            position.do_move(mv);
+        }
+
+        position.undo_move(SHUFFLE[3]);
+
+        assert!(!position.has_repeated(1));
+    }
+
+    #[test]
+    fn is_draw_fifty_moves() {
+        let position = Position::from_fen(Ruleset::Standard,
+            b"4k3/8/8/8/8/8/8/4K2R w - - 100 80");
+
+        assert!(position.is_draw(0));
+
+        let mut position = Position::from_fen(Ruleset::Standard,
+            b"4k3/8/8/8/8/8/8/4K2R w - - 99 80");
+
+        assert!(!position.is_draw(0));
+
+        position.do_move(Move::new(Square::H1, Square::H2));
+
+        assert!(position.is_draw(0));
+    }
+
+    #[test]
+    fn is_draw_fifty_moves_checkmate() {
+        let position = Position::from_fen(Ruleset::Standard,
+            b"7k/6Q1/6K1/8/8/8/8/8 b - - 100 80");
+
+        assert!(!position.is_draw(0));
+
+        // but check alone doesn't prevent the draw
+        let position = Position::from_fen(Ruleset::Standard,
+            b"7k/8/6K1/8/8/8/8/7Q b - - 100 80");
+
+        assert!(position.is_draw(0));
+    }
+
+    #[test]
+    fn has_insufficient_material() {
+        for fen in [
+            "4k3/8/8/8/8/8/8/4K3 w - - 0 1",
+            "4k3/8/8/8/8/8/8/4KN2 w - - 0 1",
+            "4k3/8/8/8/8/8/8/4KB2 w - - 0 1",
+            "4kb2/8/8/8/8/8/8/2B1K3 w - - 0 1",
+            "4k3/8/8/8/8/8/B7/1B2K3 w - - 0 1",
+        ] {
+            let position = Position::from_fen(Ruleset::Standard, fen.as_bytes());
+
+            assert!(position.has_insufficient_material(), "{fen}");
+        }
+
+        for fen in [
+            "4k3/8/8/8/8/8/8/2B1KB2 w - - 0 1",
+            "2b1k3/8/8/8/8/8/8/2B1K3 w - - 0 1",
+            "4kn2/8/8/8/8/8/8/4KN2 w - - 0 1",
+            "4k3/8/8/8/8/8/8/4KBN1 w - - 0 1",
+            "4k3/8/8/8/8/8/4P3/4K3 w - - 0 1",
+            "4k3/8/8/8/8/8/8/4K2R w - - 0 1",
+        ] {
+            let position = Position::from_fen(Ruleset::Standard, fen.as_bytes());
+
+            assert!(!position.has_insufficient_material(), "{fen}");
+        }
+    }
+
+    #[test]
+    fn has_insufficient_material_crazyhouse() {
+        let position = Position::from_fen(Ruleset::Crazyhouse,
+            b"4k3/8/8/8/8/8/8/4K3[] w - - 0 1");
+
+        assert!(position.has_insufficient_material());
+
+        // any piece can be captured and dropped elsewhere, even a lone minor
+        // piece, and pieces in a pocket can always come back
+        for fen in [
+            "4k3/8/8/8/8/8/8/4KN2[] w - - 0 1",
+            "4k3/8/8/8/8/8/8/4K3[n] w - - 0 1",
+        ] {
+            let position = Position::from_fen(Ruleset::Crazyhouse, fen.as_bytes());
+
+            assert!(!position.has_insufficient_material(), "{fen}");
+        }
+    }
+
+    #[test]
+    fn has_insufficient_material_antichess() {
+        for fen in [
+            // bishops on opposite colors can never capture one another
+            "6b1/8/8/8/8/8/8/2B5 w - - 0 1",
+            "6b1/8/2b5/8/8/8/8/B7 w - - 0 1",
+        ] {
+            let position = Position::from_fen(Ruleset::Antichess, fen.as_bytes());
+
+            assert!(position.has_insufficient_material(), "{fen}");
+        }
+
+        for fen in [
+            // but two bare kings must eventually meet, and a side with nothing
+            // left has already won
+            "5b2/8/8/8/8/8/8/2B5 w - - 0 1",
+            "6b1/8/8/8/8/8/8/2BB4 w - - 0 1",
+            "4k3/8/8/8/8/8/8/4K3 w - - 0 1",
+            "8/8/8/8/8/8/8/2B5 w - - 0 1",
+        ] {
+            let position = Position::from_fen(Ruleset::Antichess, fen.as_bytes());
+
+            assert!(!position.has_insufficient_material(), "{fen}");
+        }
+    }
+
+    #[test]
+    fn has_insufficient_material_three_check() {
+        let position = Position::from_fen(Ruleset::ThreeCheck,
+            b"4k3/8/8/8/8/8/8/4K3 w - - 0 1 +0+0");
+
+        assert!(position.has_insufficient_material());
+
+        // even a lone knight can give check three times
+        let position = Position::from_fen(Ruleset::ThreeCheck,
+            b"4k3/8/8/8/8/8/8/4KN2 w - - 0 1 +0+0");
+
+        assert!(!position.has_insufficient_material());
+    }
+
+    #[test]
+    fn has_insufficient_material_king_of_the_hill() {
+        // either bare king may still reach the center
+        let position = Position::from_fen(Ruleset::KingOfTheHill,
+            b"4k3/8/8/8/8/8/8/4K3 w - - 0 1");
+
+        assert!(!position.has_insufficient_material());
+    }
+
+    #[test]
+    fn has_insufficient_material_horde_and_racing_kings() {
+        // black can still win by capturing the last of the horde
+        let position = Position::from_fen(Ruleset::Horde,
+            b"4k3/8/8/8/8/8/8/B7 w - - 0 1");
+
+        assert!(!position.has_insufficient_material());
+
+        let position = Position::from_fen(Ruleset::RacingKings,
+            b"8/8/8/8/8/8/k6K/8 w - - 0 1");
+
+        assert!(!position.has_insufficient_material());
+    }
+
+    #[test]
+    fn has_insufficient_material_atomic() {
+        for fen in [
+            "4k3/8/8/8/8/8/8/4K3 w - - 0 1",
+            "4k3/8/8/8/8/8/8/4KN2 w - - 0 1",
+            "4k3/8/8/8/8/8/8/3NKN2 w - - 0 1",
+
+            // bishops on opposite colors can never capture one another
+            "4k1b1/8/8/8/8/8/8/2B1K3 w - - 0 1",
+        ] {
+            let position = Position::from_fen(Ruleset::Atomic, fen.as_bytes());
+
+            assert!(position.has_insufficient_material(), "{fen}");
+        }
+
+        for fen in [
+            // but bishops on the same color can, taking the king with them
+            "4kb2/8/8/8/8/8/8/2B1K3 w - - 0 1",
+            "4k3/8/8/8/8/8/8/3RKR2 w - - 0 1",
+            "4k3/8/8/8/8/8/8/4KQ2 w - - 0 1",
+            "4k3/8/8/8/8/8/4P3/4K3 w - - 0 1",
+        ] {
+            let position = Position::from_fen(Ruleset::Atomic, fen.as_bytes());
+
+            assert!(!position.has_insufficient_material(), "{fen}");
+        }
+    }
+}
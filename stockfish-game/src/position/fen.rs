@@ -1,5 +1,12 @@
+mod error;
+
+pub use error::{FenError, FenField};
+
 use crate::prelude::*;
 use stockfish_core::prelude::*;
+use stockfish_core::hash::Zobrist;
+
+use core::fmt::{Display, Formatter, Result as FmtResult, Write};
 
 #[allow(clippy::multiple_inherent_impl)]
 impl Position {
@@ -51,24 +58,182 @@ impl Position {
         //
         // 6) Fullmove number. The number of the full move. It starts at 1, and
         //    is incremented after Black's move.
-        let mut position = Position::empty(ruleset);
-        let mut fields   = fen.split(|b| *b == b' ');
+        //
+        // In crazyhouse, the piece placement is followed by the pieces in each
+        // side's pocket in square brackets (e.g., "[Qn]"), and promoted pieces
+        // on the board are followed by a tilde.
+        //
+        // In three-check, the fullmove number is followed by the number of
+        // checks given so far by white and black (e.g., "+2+0").
+        let mut fields = fen.split(|b| *b == b' ');
 
-        let board      = parse_board(fields.next().unwrap_or_default());
+        let (placement, pocket) = split_pocket(fields.next().unwrap_or_default());
+
+        let board      = parse_board(placement);
         let turn       = parse_turn(fields.next().unwrap_or_default());
         let castling   = parse_castling(fields.next().unwrap_or_default(), board);
         let en_passant = parse_en_passant(fields.next().unwrap_or_default(), turn);
         let halfmoves  = parse_move_number(fields.next().unwrap_or_default());
         let fullmoves  = parse_move_number(fields.next().unwrap_or_default());
+        let checks     = parse_checks(fields.next().unwrap_or_default());
+
+        let ply = u16::from(fullmoves.saturating_sub(1)) * 2
+            + u16::from(turn.is_black());
+
+        let position = Self::from_parts(ruleset, board, turn, castling, en_passant, halfmoves, ply);
+        let position = if ruleset == Ruleset::ThreeCheck { position.with_checks(checks) } else { position };
+
+        if ruleset == Ruleset::Crazyhouse {
+            position.with_pockets(parse_pocket(pocket.unwrap_or_default()), parse_promoted(placement))
+        } else {
+            position
+        }
+    }
+
+    /// Parses a `fen` (Forsyth-Edward Notation) string into a [`Position`],
+    /// returning a [`FenError`] describing the first problem encountered if
+    /// the string is malformed.
+    ///
+    /// Unlike [`Position::from_fen`], this never panics and never silently
+    /// discards invalid input, so it's suitable for FEN strings that come from
+    /// untrusted sources. The halfmove clock and fullmove number may be
+    /// omitted, in which case they default to `0` and `1` respectively. A
+    /// halfmove clock beyond `255` is kept as `255`, which is still well past
+    /// the point where the game is drawn.
+    ///
+    /// An en passant square that could have resulted from the last move but
+    /// which can't actually be captured on is accepted but discarded, per
+    /// X-FEN.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if any field of the FEN string is malformed, if any
+    /// fields follow the last one, or if either side is missing a king
+    /// outside of antichess. The white horde has no king in horde.
+    pub fn try_from_fen(ruleset: Ruleset, fen: &[u8]) -> Result<Self, FenError> {
+        Self::try_parse_fen(ruleset, fen, false)
+    }
+
+    /// Behaves identically to [`Position::try_from_fen`], except that if the
+    /// castling field uses Shredder-FEN file letters (e.g., `HAha`) a
+    /// position requested under [`Ruleset::Standard`] is parsed under
+    /// [`Ruleset::Chess960`] instead. Any other `ruleset` is kept as it is.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error under the same conditions as
+    /// [`Position::try_from_fen`].
+    pub fn try_from_fen_inferring_chess960(ruleset: Ruleset, fen: &[u8]) -> Result<Self, FenError> {
+        Self::try_parse_fen(ruleset, fen, true)
+    }
+
+    fn try_parse_fen(ruleset: Ruleset, fen: &[u8], infer_chess960: bool) -> Result<Self, FenError> {
+        // pair each field with the offset it begins at; missing fields are
+        // treated as empty ones at the very end of the string
+        let mut fields = fen.split(|b| *b == b' ').scan(0, |offset, field| {
+            let start = *offset;
+            *offset  += field.len() + 1;
+
+            Some((start, field))
+        });
+
+        let mut next_field = || fields.next().unwrap_or((fen.len(), b""));
+
+        let (offset, bytes)     = next_field();
+        let (placement, pocket) = split_pocket(bytes);
+        let (board, promoted)   = try_parse_board(placement, offset, ruleset)?;
+
+        let pockets = match pocket {
+            Some(pocket) => try_parse_pocket(pocket, offset + placement.len(), ruleset)?,
+            None         => [0; Piece::COUNT],
+        };
+
+        // kings are ordinary pieces in antichess, so either side may have any
+        // number of them, and the white horde doesn't need one at all
+        for color in Color::iter().filter(|&color| {
+            ruleset != Ruleset::Antichess && !(ruleset == Ruleset::Horde && color.is_white())
+        }) {
+            if board.iter().all(|(_, piece)| piece != color | Token::King) {
+                return Err(FenError::MissingKing { field: FenField::Board, offset, color });
+            }
+        }
+
+        let (offset, bytes) = next_field();
+        let turn            = try_parse_turn(bytes, offset)?;
+
+        let (offset, bytes) = next_field();
+        let castling        = try_parse_castling(bytes, offset, board)?;
+        let shredder        = bytes.iter().any(|b| matches!(b, b'A'..=b'H' | b'a'..=b'h'));
+
+        let (offset, bytes) = next_field();
+        let en_passant      = try_parse_en_passant(bytes, offset, board, turn)?;
+
+        let (offset, bytes) = next_field();
+        let halfmoves       = try_parse_clock(bytes, offset, FenField::Halfmoves, 0_u32)?;
+        let halfmoves       = u8::try_from(halfmoves).unwrap_or(u8::MAX);
+
+        let (offset, bytes) = next_field();
+        let fullmoves       = try_parse_clock(bytes, offset, FenField::Fullmoves, 1_u16)?;
+
+        let ply = fullmoves.saturating_sub(1).checked_mul(2)
+            .and_then(|ply| ply.checked_add(u16::from(turn.is_black())))
+            .ok_or(FenError::InvalidClock { field: FenField::Fullmoves, offset })?;
+
+        let checks = if ruleset == Ruleset::ThreeCheck {
+            let (offset, bytes) = next_field();
+
+            try_parse_checks(bytes, offset)?
+        } else {
+            [0; Color::COUNT]
+        };
+
+        // nothing may follow the last field, which is the checks given in
+        // three-check and the fullmove number otherwise, so anything more is
+        // reported as a malformed last field
+        let (offset, bytes) = next_field();
+
+        if !bytes.is_empty() {
+            return Err(if ruleset == Ruleset::ThreeCheck {
+                FenError::InvalidChecks { field: FenField::Checks, offset }
+            } else {
+                FenError::InvalidClock { field: FenField::Fullmoves, offset }
+            });
+        }
+
+        let ruleset = if infer_chess960 && shredder && ruleset == Ruleset::Standard {
+            Ruleset::Chess960
+        } else {
+            ruleset
+        };
+
+        Ok(Self::from_parts(ruleset, board, turn, castling, en_passant, halfmoves, ply)
+            .with_pockets(pockets, promoted)
+            .with_checks(checks))
+    }
+
+    /// Assembles a position from the individually-parsed fields of a FEN.
+    pub(crate) fn from_parts(
+        ruleset:    Ruleset,
+        board:      Board,
+        turn:       Color,
+        castling:   [Option<CastlingPath>; CastlingVariety::COUNT],
+        en_passant: Option<Square>,
+        halfmoves:  u8,
+        ply:        u16,
+    ) -> Self {
+        let mut position = Position::empty(ruleset);
 
         for (square, piece) in board.iter() {
             position.emplace(piece, square);
         }
 
-        position.turn            = turn;
+        position.turn = turn;
+
+        // there's no castling in antichess, so any rights are simply ignored
+        let castling = if ruleset == Ruleset::Antichess { Default::default() } else { castling };
 
-        position.castling_paths  = castling;
-        position.castling_rights = castling
+        position.castling_paths        = castling;
+        position.state.castling_rights = castling
             .iter()
             .flatten()
             .fold(CastlingRights::NONE, |rights, path| rights | path.rights() );
@@ -84,7 +249,7 @@ impl Position {
         // a) side to move has a pawn threatening the en passant square,
         // b) there is an enemy pawn in front of the en passant square, and
         // c) there is no piece on or behind the en passant square
-        position.en_passant = en_passant.filter(|square| {
+        position.state.en_passant = en_passant.filter(|square| {
             let good_turn = turn;
             let evil_turn = !turn;
             let good_pawn = Piece::new(good_turn, Token::Pawn);
@@ -107,12 +272,397 @@ impl Position {
                     .omits(square.wrapping_sub(evil_turn.direction()))
         });
 
-        position.halfmoves = halfmoves;
-        position.ply       = fullmoves.saturating_sub(1) * 2
-            + u8::from(turn.is_black());
+        position.state.halfmoves = halfmoves;
+        position.ply             = ply;
+
+        position.state.keys = position.compute_keys();
+        position.update_check_info();
 
         position
     }
+
+    /// Fills each side's pocket and marks the `promoted` pieces on the board
+    /// of a crazyhouse position assembled by [`Position::from_parts`].
+    pub(crate) fn with_pockets(mut self, pockets: [u8; Piece::COUNT], promoted: Bitboard) -> Self {
+        for piece in Piece::iter() {
+            for _ in 0..pockets[piece] {
+                self.add_to_pocket(piece);
+            }
+        }
+
+        // only knights, bishops, rooks, and queens can have been promoted
+        let promotable = self.bb_all & !(self.bb_by_piece[Token::Pawn] | self.bb_by_piece[Token::King]);
+
+        self.promoted = promoted & promotable;
+
+        self
+    }
+
+    /// Records the `checks` already given by each side in a three-check
+    /// position assembled by [`Position::from_parts`].
+    pub(crate) fn with_checks(mut self, checks: [u8; Color::COUNT]) -> Self {
+        for color in Color::iter() {
+            for count in 0..checks[color] {
+                self.state.keys.toggle_checks(color, count);
+            }
+        }
+
+        self.state.checks = checks;
+
+        self
+    }
+}
+
+#[allow(clippy::multiple_inherent_impl)]
+impl Position {
+    /// Serializes the position into a FEN (Forsyth-Edwards Notation) string
+    /// which, when parsed with [`Position::from_fen`] under the same
+    /// [`Ruleset`], results in an identical position.
+    ///
+    /// Castling rights are written as `KQkq` for standard chess. For Chess960
+    /// they're written in Shredder-FEN style, using the file of each castling
+    /// rook, since that's unambiguous regardless of where the rooks are. The
+    /// file is also used outside of Chess960 for any castling rook that isn't
+    /// the outermost one on its side of the king, which is all that `K` or `Q`
+    /// could refer to.
+    ///
+    /// For crazyhouse, the pieces in each side's pocket are written in square
+    /// brackets after the piece placement, and promoted pieces are followed by
+    /// a tilde. For three-check, the checks given by each side are written
+    /// after the fullmove number as `+N+M`.
+    #[must_use]
+    pub fn to_fen(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl Display for Position {
+    // the fullmove number is the ply halved and rounded down, plus one
+    #[allow(clippy::integer_division)]
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        // 1) piece placement, from rank 8 down to rank 1
+        for rank in Rank::iter().rev() {
+            let mut empty = 0_u8;
+
+            for file in File::iter() {
+                let Some(piece) = self[Square::new(file, rank)] else {
+                    empty += 1;
+                    continue;
+                };
+
+                if empty > 0 {
+                    write!(f, "{empty}")?;
+                    empty = 0;
+                }
+
+                f.write_char(piece.into())?;
+
+                if self.promoted.contains(Square::new(file, rank)) {
+                    f.write_char('~')?;
+                }
+            }
+
+            if empty > 0 {
+                write!(f, "{empty}")?;
+            }
+
+            if rank != Rank::_1 {
+                f.write_char('/')?;
+            }
+        }
+
+        if self.ruleset == Ruleset::Crazyhouse {
+            f.write_char('[')?;
+
+            for color in Color::iter() {
+                for token in Token::iter().rev() {
+                    for _ in 0..self.pockets[color | token] {
+                        f.write_char((color | token).into())?;
+                    }
+                }
+            }
+
+            f.write_char(']')?;
+        }
+
+        // 2) active color
+        f.write_str(if self.turn.is_white() { " w " } else { " b " })?;
+
+        // 3) castling availability
+        let mut castling = false;
+
+        for variety in CastlingVariety::iter() {
+            let Some(path) = self.castling_paths[variety] else { continue };
+
+            if !self.can_castle(variety.rights()) {
+                continue;
+            }
+
+            // `K` and `Q` are read back as the outermost rook on that side of
+            // the king, so any other rook has to be named by its file
+            let rook      = path.rook_origin();
+            let rank      = variety.color().rank();
+            let piece     = Piece::new(variety.color(), Token::Rook);
+            let outermost = match variety.side() {
+                CastlingSide::King  => self.board.search(rank.into_iter().rev(), piece),
+                CastlingSide::Queen => self.board.search(rank.into_iter(),       piece),
+            };
+
+            let letter = match (self.ruleset, variety.side()) {
+                (Ruleset::Chess960, _)       => char::from(rook.file()),
+                _ if outermost != Some(rook) => char::from(rook.file()),
+                (_, CastlingSide::King)      => 'K',
+                (_, CastlingSide::Queen)     => 'Q',
+            };
+
+            f.write_char(if variety.color().is_white() {
+                letter
+            } else {
+                letter.to_ascii_lowercase()
+            })?;
+
+            castling = true;
+        }
+
+        if !castling {
+            f.write_char('-')?;
+        }
+
+        // 4) en passant target square
+        match self.en_passant() {
+            Some(square) => write!(f, " {}{}",
+                char::from(square.file()).to_ascii_lowercase(),
+                char::from(square.rank()),
+            )?,
+
+            None => f.write_str(" -")?,
+        }
+
+        // 5) and 6) halfmove clock and fullmove number
+        write!(f, " {} {}", self.halfmoves(), self.ply / 2 + 1)?;
+
+        if self.ruleset == Ruleset::ThreeCheck {
+            write!(f, " +{}+{}", self.state.checks[Color::White], self.state.checks[Color::Black])?;
+        }
+
+        Ok(())
+    }
+}
+
+fn try_parse_board(bytes: &[u8], offset: usize, ruleset: Ruleset) -> Result<(Board, Bitboard), FenError> {
+    let mut board    = Board::EMPTY;
+    let mut promoted = Bitboard::EMPTY;
+    let mut ranks    = Rank::iter().rev();
+    let mut rank     = ranks.next();
+    let mut file     = 0_usize;
+    let mut last     = None;
+
+    for (i, &byte) in bytes.iter().enumerate() {
+        let offset   = offset + i;
+        let overflow = FenError::RankOverflow { field: FenField::Board, offset };
+        let invalid  = FenError::InvalidPiece { field: FenField::Board, offset, byte };
+
+        match byte {
+            b'/' => {
+                if file != File::COUNT {
+                    return Err(overflow);
+                }
+
+                rank = Some(ranks.next().ok_or(overflow)?);
+                file = 0;
+                last = None;
+            },
+
+            b'1'..=b'8' => {
+                file += usize::from(byte - b'0');
+                last  = None;
+
+                if file > File::COUNT {
+                    return Err(overflow);
+                }
+            },
+
+            // in crazyhouse, a tilde marks the piece just placed as having
+            // been promoted from a pawn
+            b'~' => {
+                let square = last
+                    .filter(|_| ruleset == Ruleset::Crazyhouse)
+                    .filter(|&square: &Square| board[square].map_or(false, |piece| {
+                        !matches!(piece.token(), Token::Pawn | Token::King)
+                    }))
+                    .ok_or(invalid)?;
+
+                promoted |= square;
+                last      = None;
+            },
+
+            _ => {
+                let piece = Piece::from_fen(byte).ok_or(invalid)?;
+
+                let square_file = File::VARIANTS.get(file).copied().ok_or(overflow)?;
+                let square_rank = rank.ok_or(overflow)?;
+                let square      = Square::new(square_file, square_rank);
+
+                board[square] = Some(piece);
+                file         += 1;
+                last          = Some(square);
+            },
+        }
+    }
+
+    // the last rank must be complete too, and no ranks may be missing
+    if file != File::COUNT || ranks.next().is_some() {
+        return Err(FenError::RankOverflow { field: FenField::Board, offset: offset + bytes.len() });
+    }
+
+    Ok((board, promoted))
+}
+
+fn try_parse_pocket(bytes: &[u8], offset: usize, ruleset: Ruleset) -> Result<[u8; Piece::COUNT], FenError> {
+    let mut pockets = [0_u8; Piece::COUNT];
+
+    let invalid = FenError::InvalidPocket { field: FenField::Board, offset };
+    let pieces  = bytes
+        .strip_prefix(b"[")
+        .and_then(|bytes| bytes.strip_suffix(b"]"))
+        .filter(|_| ruleset == Ruleset::Crazyhouse)
+        .ok_or(invalid)?;
+
+    for (i, &byte) in pieces.iter().enumerate() {
+        let offset = offset + i + 1;
+        let piece  = Piece::from_fen(byte)
+            .filter(|piece| piece.token() != Token::King)
+            .ok_or(FenError::InvalidPiece { field: FenField::Board, offset, byte })?;
+
+        if usize::from(pockets[piece]) >= Zobrist::POCKET_CAPACITY {
+            return Err(FenError::InvalidPocket { field: FenField::Board, offset });
+        }
+
+        pockets[piece] += 1;
+    }
+
+    Ok(pockets)
+}
+
+fn try_parse_turn(bytes: &[u8], offset: usize) -> Result<Color, FenError> {
+    match bytes {
+        b"w" => Ok(Color::White),
+        b"b" => Ok(Color::Black),
+        _    => Err(FenError::InvalidTurn { field: FenField::Turn, offset }),
+    }
+}
+
+fn try_parse_castling(bytes: &[u8], offset: usize, board: Board) -> Result<[Option<CastlingPath>; 4], FenError> {
+    let mut paths = [None; 4];
+
+    if bytes == b"-" {
+        return Ok(paths);
+    }
+
+    // the field may not be left out, even when there are no rights to
+    // castle, and is reported as though it were a space
+    if bytes.is_empty() {
+        return Err(FenError::InvalidCastling { field: FenField::Castling, offset, byte: b' ' });
+    }
+
+    for (i, &byte) in bytes.iter().enumerate() {
+        let offset = offset + i;
+        let color  = if byte.is_ascii_uppercase() { Color::White } else { Color::Black };
+        let king   = color | Token::King;
+        let rook   = color | Token::Rook;
+        let rank   = color.rank();
+
+        let k_file = board.search(rank.into_iter(), king).map(Square::file);
+
+        // for the traditional letters, use the outermost rook on that side;
+        // for file letters, the rook must be exactly where it says
+        let (r_file, side) = match byte {
+            b'K' | b'k' => (board.search(rank.into_iter().rev(), rook).map(Square::file), Some(CastlingSide::King)),
+            b'Q' | b'q' => (board.search(rank.into_iter(),       rook).map(Square::file), Some(CastlingSide::Queen)),
+
+            b'A'..=b'H' | b'a'..=b'h' => (
+                File::from_fen(byte).filter(|&file| board[Square::new(file, rank)] == Some(rook)),
+                None,
+            ),
+
+            _ => return Err(FenError::InvalidCastling { field: FenField::Castling, offset, byte }),
+        };
+
+        let path = k_file
+            .zip(r_file)
+            .and_then(|(k, r)| CastlingPath::new(color, k, r))
+            .filter(|path| side.map_or(true, |side| side == path.side()))
+            .ok_or(FenError::CastlingWithoutRook { field: FenField::Castling, offset, byte })?;
+
+        // each right may only be given once, by whichever letter
+        if paths[path.variety()].is_some() {
+            return Err(FenError::InvalidCastling { field: FenField::Castling, offset, byte });
+        }
+
+        paths[path.variety()] = Some(path);
+    }
+
+    Ok(paths)
+}
+
+fn try_parse_en_passant(bytes: &[u8], offset: usize, board: Board, turn: Color) -> Result<Option<Square>, FenError> {
+    let error = FenError::InvalidEnPassant { field: FenField::EnPassant, offset };
+
+    if bytes == b"-" {
+        return Ok(None);
+    }
+
+    let square = Some(bytes)
+        .filter(|bytes| bytes.len() == 2)
+        .and_then(|bytes| parse_en_passant(bytes, turn))
+        .ok_or(error)?;
+
+    // the enemy pawn must be on the square it would have jumped to, with
+    // both the square it jumped over and the square it came from empty
+    let them = !turn;
+    let pawn = board[square.wrapping_add(them.direction())] == Some(them | Token::Pawn);
+    let open = board[square].is_none() && board[square.wrapping_sub(them.direction())].is_none();
+
+    if pawn && open { Ok(Some(square)) } else { Err(error) }
+}
+
+fn try_parse_clock<T: core::str::FromStr>(bytes: &[u8], offset: usize, field: FenField, default: T) -> Result<T, FenError> {
+    if bytes.is_empty() {
+        return Ok(default);
+    }
+
+    core::str::from_utf8(bytes)
+        .ok()
+        .filter(|clock| clock.bytes().all(|b| b.is_ascii_digit()))
+        .and_then(|clock| clock.parse().ok())
+        .ok_or(FenError::InvalidClock { field, offset })
+}
+
+/// Parses the checks given by each side in three-check, written as `+N+M`. A
+/// missing field means no checks have been given.
+fn try_parse_checks(bytes: &[u8], offset: usize) -> Result<[u8; Color::COUNT], FenError> {
+    if bytes.is_empty() {
+        return Ok([0; Color::COUNT]);
+    }
+
+    match *bytes {
+        [b'+', white @ b'0'..=b'3', b'+', black @ b'0'..=b'3'] => Ok([white - b'0', black - b'0']),
+        _ => Err(FenError::InvalidChecks { field: FenField::Checks, offset }),
+    }
+}
+
+/// Splits the piece placement field into the placement itself and the pocket
+/// following it, if there is one, brackets included.
+fn split_pocket(bytes: &[u8]) -> (&[u8], Option<&[u8]>) {
+    match bytes.iter().position(|&b| b == b'[') {
+        Some(i) => {
+            let (placement, pocket) = bytes.split_at(i);
+
+            (placement, Some(pocket))
+        },
+
+        None => (bytes, None),
+    }
 }
 
 fn parse_board(bytes: &[u8]) -> Board {
@@ -138,6 +688,10 @@ fn parse_board(bytes: &[u8]) -> Board {
             // number of files
             b'1'..=b'8' => (origin, square + (byte - b'0') as usize),
 
+            // a tilde marks the previous piece as promoted, which is handled
+            // separately by `parse_promoted`
+            b'~' => (origin, square),
+
             // any other byte should be treated as a piece
             _ => {
                 // TODO: not overflowing the board or rank should be
@@ -155,6 +709,44 @@ fn parse_board(bytes: &[u8]) -> Board {
     board
 }
 
+fn parse_promoted(bytes: &[u8]) -> Bitboard {
+    let mut promoted = Bitboard::EMPTY;
+
+    // this walks the board exactly as `parse_board` does, marking the square
+    // behind each tilde
+    let _ = bytes.iter().copied().fold((56_usize, 56_usize), |(origin, square), byte| {
+        match byte {
+            b'/'        => (origin.saturating_sub(8), origin.saturating_sub(8)),
+            b'1'..=b'8' => (origin, square + usize::from(byte - b'0')),
+
+            b'~' => {
+                if let Some(&promoted_square) = square.checked_sub(1).and_then(|s| Square::VARIANTS.get(s)) {
+                    promoted |= promoted_square;
+                }
+
+                (origin, square)
+            },
+
+            _ => (origin, square + 1),
+        }
+    });
+
+    promoted
+}
+
+fn parse_pocket(bytes: &[u8]) -> [u8; Piece::COUNT] {
+    let mut pockets = [0_u8; Piece::COUNT];
+
+    // anything that isn't a piece, including the brackets, is ignored
+    for piece in bytes.iter().copied().filter_map(Piece::from_fen) {
+        if piece.token() != Token::King && usize::from(pockets[piece]) < Zobrist::POCKET_CAPACITY {
+            pockets[piece] += 1;
+        }
+    }
+
+    pockets
+}
+
 fn parse_turn(bytes: &[u8]) -> Color {
     debug_assert_eq!(1, bytes.len());
     debug_assert!(bytes[0] == b'w' || bytes[0] == b'b');
@@ -223,6 +815,14 @@ fn parse_en_passant(fen: &[u8], turn: Color) -> Option<Square> {
     }).map (|(f, r)| Square::new(f, r))
 }
 
+fn parse_checks(bytes: &[u8]) -> [u8; Color::COUNT] {
+    // anything malformed is treated as no checks having been given
+    match *bytes {
+        [b'+', white @ b'0'..=b'3', b'+', black @ b'0'..=b'3'] => [white - b'0', black - b'0'],
+        _                                                      => [0; Color::COUNT],
+    }
+}
+
 fn parse_move_number(fen: &[u8]) -> u8 {
     // TODO: this parses values like "08", which is not to spec
     str::parse(
@@ -519,11 +1119,11 @@ mod tests {
 
         assert!(position.turn.is_white());
 
-        assert_eq!(CastlingRights::ANY, position.castling_rights);
+        assert_eq!(CastlingRights::ANY, position.castling_rights());
 
-        assert_eq!(None, position.en_passant);
-        assert_eq!(0,    position.ply);
-        assert_eq!(0,    position.halfmoves);
+        assert_eq!(None, position.en_passant());
+        assert_eq!(0,    position.ply());
+        assert_eq!(0,    position.halfmoves());
 
         assert_eq!(32, position.bitboard().count());
 
@@ -552,11 +1152,11 @@ mod tests {
 
         assert!(position.turn.is_white());
 
-        assert_eq!(CastlingRights::ANY, position.castling_rights);
+        assert_eq!(CastlingRights::ANY, position.castling_rights());
 
-        assert_eq!(Square::D6, position.en_passant.unwrap());
-        assert_eq!(10,         position.ply);
-        assert_eq!(0,          position.halfmoves);
+        assert_eq!(Square::D6, position.en_passant().unwrap());
+        assert_eq!(10,         position.ply());
+        assert_eq!(0,          position.halfmoves());
 
         assert_eq!(30, position.bitboard().count());
 
@@ -574,4 +1174,497 @@ mod tests {
         assert_eq!(1,  position.count_by_token[Piece::WhiteKing]);
         assert_eq!(7,  position.count_by_token[Piece::WhitePawn]);
     }
+
+    /// Positions used throughout the test suite, which must all survive being
+    /// written out and parsed back in.
+    const CORPUS: &[(Ruleset, &str)] = &[
+        (Ruleset::Standard, "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"),
+        (Ruleset::Standard, "rnbqkb1r/ppp2ppp/8/3pP3/3Qn3/5N2/PPP2PPP/RNB1KB1R w KQkq d6 0 6"),
+        (Ruleset::Standard, "rnbqkbnr/pp1ppppp/8/2p5/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 1 2"),
+        (Ruleset::Standard, "rn1q1rk1/1p2bppp/p2pbn2/4p3/4P3/1NN1BP2/PPPQ2PP/2KR1B1R b - - 3 11"),
+        (Ruleset::Standard, "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1"),
+        (Ruleset::Standard, "r3k2r/p1ppqpb1/bn2pnp1/3PN3/Pp2P3/2N2Q1p/1PPBBPPP/R3K2R b KQkq a3 0 1"),
+        (Ruleset::Standard, "r3k2r/8/8/8/8/8/8/R3K2R w Kq - 0 1"),
+        (Ruleset::Standard, "n1n5/PPPk4/8/8/8/8/4Kppp/5N1N b - - 0 1"),
+        (Ruleset::Standard, "4k3/4r3/8/8/1b6/8/3NN3/4K3 w - - 0 1"),
+        (Ruleset::Standard, "8/8/8/2k5/3Pp3/8/8/4K3 b - d3 0 1"),
+        (Ruleset::Standard, "8/4k3/8/8/8/8/8/4K3 b - - 99 250"),
+        (Ruleset::Chess960, "1r2k1r1/8/8/8/8/8/8/1R2K1R1 w GBgb - 0 1"),
+        (Ruleset::Chess960, "4k3/8/8/8/8/8/8/5KR1 w G - 0 1"),
+        (Ruleset::Chess960, "4k3/8/8/8/8/8/8/qRK5 w B - 0 1"),
+        (Ruleset::Chess960, "nrk3r1/ppp1pp1p/3p2p1/5bn1/P7/2N2B2/1PPPPP2/2KBN1RR w Ggb - 0 1"),
+        (Ruleset::Chess960, "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9"),
+        (Ruleset::Crazyhouse, "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR[] w KQkq - 0 1"),
+        (Ruleset::Crazyhouse, "r1bqk2r/pppp1ppp/2n1p3/4P3/1b1Pn3/2NB1N2/PPP2PPP/R1BQK2R[] b KQkq - 0 1"),
+        (Ruleset::Crazyhouse, "4k3/1Q~6/8/8/4b3/8/Kpp5/8[] b - - 0 1"),
+        (Ruleset::Crazyhouse, "2k5/8/8/8/8/8/8/4K3[QRBNPqrbnp] w - - 0 1"),
+        (Ruleset::Antichess,  "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w - - 0 1"),
+        (Ruleset::Antichess,  "8/1p6/8/8/8/8/P7/8 w - - 0 1"),
+        (Ruleset::Antichess,  "4k3/8/8/8/8/8/8/1K2K1K1 b - - 0 40"),
+        (Ruleset::ThreeCheck, "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1 +0+0"),
+        (Ruleset::ThreeCheck, "r1bqkbnr/pppp1ppp/2n5/1B2p3/4P3/5N2/PPPP1PPP/RNBQK2R b KQkq - 3 3 +2+1"),
+        (Ruleset::KingOfTheHill, "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"),
+        (Ruleset::Horde, "rnbqkbnr/pppppppp/8/1PP2PP1/PPPPPPPP/PPPPPPPP/PPPPPPPP/PPPPPPPP w kq - 0 1"),
+        (Ruleset::Horde, "4k3/8/8/8/8/8/8/P7 b - - 0 1"),
+        (Ruleset::RacingKings, "8/8/8/8/8/8/krbnNBRK/qrbnNBRQ w - - 0 1"),
+    ];
+
+    #[test]
+    fn to_fen_start_position() {
+        let fen      = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+        let position = Position::from_fen(Ruleset::Standard, fen.as_bytes());
+
+        assert_eq!(fen, position.to_fen());
+        assert_eq!(fen, format!("{position}"));
+    }
+
+    #[test]
+    fn to_fen_start_fen() {
+        // Chess960 is left out, since its castling rights are always written
+        // with Shredder-FEN file letters
+        for ruleset in [
+            Ruleset::Standard,
+            Ruleset::Crazyhouse,
+            Ruleset::Atomic,
+            Ruleset::Antichess,
+            Ruleset::ThreeCheck,
+            Ruleset::KingOfTheHill,
+            Ruleset::Horde,
+            Ruleset::RacingKings,
+        ] {
+            let position = Position::try_from_fen(ruleset, ruleset.start_fen().as_bytes()).unwrap();
+
+            assert_eq!(ruleset.start_fen(), position.to_fen());
+            assert_eq!(Ok(()), position.validate(), "{ruleset:?}");
+        }
+    }
+
+    #[test]
+    fn to_fen_chess960_shredder() {
+        let position = Position::from_fen(Ruleset::Chess960,
+            b"nrk3r1/ppp1pp1p/3p2p1/5bn1/P7/2N2B2/1PPPPP2/2KBN1RR w Gkq - 0 1");
+
+        assert_eq!(
+            "nrk3r1/ppp1pp1p/3p2p1/5bn1/P7/2N2B2/1PPPPP2/2KBN1RR w Ggb - 0 1",
+            position.to_fen(),
+        );
+    }
+
+    #[test]
+    fn to_fen_inner_rook() {
+        // `K` would refer to the rook on h1 rather than the one castling
+        let fen      = "4k3/8/8/8/8/8/8/4KR1R w F - 0 1";
+        let position = Position::from_fen(Ruleset::Standard, fen.as_bytes());
+
+        assert_eq!(fen, position.to_fen());
+        assert_eq!(position, Position::from_fen(Ruleset::Standard, position.to_fen().as_bytes()));
+    }
+
+    #[test]
+    fn to_fen_after_moves() {
+        let mut position = Position::from_fen(Ruleset::Standard,
+            b"rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+
+        position.do_move(Move::new(Square::E2, Square::E4));
+        position.do_move(Move::new(Square::G8, Square::F6));
+        position.do_move(Move::new(Square::E1, Square::E2));
+
+        assert_eq!(
+            "rnbqkb1r/pppppppp/5n2/8/4P3/8/PPPPKPPP/RNBQ1BNR b kq - 2 2",
+            position.to_fen(),
+        );
+    }
+
+    #[test]
+    fn to_fen_round_trip() {
+        for &(ruleset, fen) in CORPUS {
+            let position = Position::from_fen(ruleset, fen.as_bytes());
+
+            assert_eq!(fen, position.to_fen());
+            assert_eq!(position, Position::from_fen(ruleset, position.to_fen().as_bytes()));
+        }
+    }
+
+    #[test]
+    fn to_fen_round_trip_after_moves() {
+        // every position reachable in one move from the corpus must also
+        // round-trip, which exercises castling rights being lost and en
+        // passant squares being set; the move history and castling paths
+        // for lost rights can't be represented in a FEN, so we compare the
+        // serialized form and keys instead of the positions themselves
+        for &(ruleset, fen) in CORPUS {
+            let mut position = Position::from_fen(ruleset, fen.as_bytes());
+
+            for mv in &position.legal_moves() {
+                position.do_move(*mv);
+
+                let fen    = position.to_fen();
+                let parsed = Position::from_fen(ruleset, fen.as_bytes());
+
+                assert_eq!(fen,            parsed.to_fen());
+                assert_eq!(position.key(), parsed.key(), "{fen}");
+
+                position.undo_move(*mv);
+            }
+        }
+    }
+
+    #[test]
+    fn try_from_fen_corpus() {
+        for &(ruleset, fen) in CORPUS {
+            assert_eq!(
+                Ok(Position::from_fen(ruleset, fen.as_bytes())),
+                Position::try_from_fen(ruleset, fen.as_bytes()),
+            );
+        }
+    }
+
+    #[test]
+    fn try_from_fen_missing_clocks() {
+        let position = Position::try_from_fen(Ruleset::Standard,
+            b"rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR b KQkq -").unwrap();
+
+        assert_eq!(0, position.halfmoves());
+        assert_eq!(1, position.ply());
+    }
+
+    #[test]
+    fn try_from_fen_missing_castling() {
+        assert_eq!(
+            Err(FenError::InvalidCastling { field: FenField::Castling, offset: 17, byte: b' ' }),
+            Position::try_from_fen(Ruleset::Standard, b"8/8/8/8/8/8/8/K6k w"),
+        );
+    }
+
+    #[test]
+    fn try_from_fen_missing_en_passant() {
+        assert_eq!(
+            Err(FenError::InvalidEnPassant { field: FenField::EnPassant, offset: 21 }),
+            Position::try_from_fen(Ruleset::Standard, b"8/8/8/8/8/8/8/K6k w -"),
+        );
+    }
+
+    #[test]
+    fn try_from_fen_discards_uncapturable_en_passant() {
+        let position = Position::try_from_fen(Ruleset::Standard,
+            b"4k3/8/8/8/4P3/8/8/4K3 b - e3 0 1").unwrap();
+
+        assert_eq!(None, position.en_passant());
+    }
+
+    #[test]
+    fn try_from_fen_invalid_piece() {
+        assert_eq!(
+            Err(FenError::InvalidPiece { field: FenField::Board, offset: 9, byte: b'x' }),
+            Position::try_from_fen(Ruleset::Standard, b"4k3/8/8/8x/8/8/8/4K3 w - - 0 1"),
+        );
+    }
+
+    #[test]
+    fn try_from_fen_crazyhouse() {
+        let position = Position::try_from_fen(Ruleset::Crazyhouse,
+            b"4k3/8/8/8/8/8/8/4K2Q~[QRbnpp] b - - 0 30").unwrap();
+
+        assert_eq!(1, position.pocket(Piece::WhiteQueen));
+        assert_eq!(1, position.pocket(Piece::WhiteRook));
+        assert_eq!(0, position.pocket(Piece::WhitePawn));
+        assert_eq!(1, position.pocket(Piece::BlackBishop));
+        assert_eq!(2, position.pocket(Piece::BlackPawn));
+
+        assert_eq!(Bitboard::from(Square::H1), position.promoted());
+
+        // a missing pocket is the same as an empty one
+        assert_eq!(
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR[] w KQkq - 0 1",
+            Position::from_fen(Ruleset::Crazyhouse,
+                b"rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").to_fen(),
+        );
+    }
+
+    #[test]
+    fn try_from_fen_antichess() {
+        // either side may have any number of kings, including none at all
+        let position = Position::try_from_fen(Ruleset::Antichess, b"8/8/8/8/8/8/8/1K2K1R1 b - - 0 1").unwrap();
+
+        assert_eq!(Bitboard::EMPTY, position.bitboard_for_color(Color::Black));
+        assert_eq!(Square::B1 | Square::E1, position.bitboard_for_token(Piece::WhiteKing));
+
+        // and castling rights are ignored entirely
+        let position = Position::try_from_fen(Ruleset::Antichess,
+            b"r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1").unwrap();
+
+        assert_eq!(CastlingRights::NONE, position.castling_rights());
+        assert_eq!("r3k2r/8/8/8/8/8/8/R3K2R w - - 0 1", position.to_fen());
+    }
+
+    #[test]
+    fn try_from_fen_three_check() {
+        let position = Position::try_from_fen(Ruleset::ThreeCheck,
+            b"4k3/8/8/8/8/8/8/4K2R w K - 0 1 +2+1").unwrap();
+
+        assert_eq!(2, position.checks_given(Color::White));
+        assert_eq!(1, position.checks_given(Color::Black));
+
+        // a missing field is the same as no checks having been given
+        assert_eq!("4k3/8/8/8/8/8/8/4K2R w K - 0 1 +0+0",
+            Position::from_fen(Ruleset::ThreeCheck, b"4k3/8/8/8/8/8/8/4K2R w K - 0 1").to_fen());
+
+        // no side can have given more than three checks
+        assert_eq!(
+            Err(FenError::InvalidChecks { field: FenField::Checks, offset: 31 }),
+            Position::try_from_fen(Ruleset::ThreeCheck, b"4k3/8/8/8/8/8/8/4K2R w K - 0 1 +4+0"),
+        );
+
+        assert_eq!(
+            Err(FenError::InvalidChecks { field: FenField::Checks, offset: 31 }),
+            Position::try_from_fen(Ruleset::ThreeCheck, b"4k3/8/8/8/8/8/8/4K2R w K - 0 1 3+3"),
+        );
+    }
+
+    #[test]
+    fn try_from_fen_horde() {
+        let fen = b"4k3/8/8/8/8/8/8/PPPPPPPP w - - 0 1";
+
+        // the horde has no king
+        assert_eq!(None, Position::try_from_fen(Ruleset::Horde, fen).unwrap().king_square(Color::White));
+
+        assert_eq!(
+            Err(FenError::MissingKing { field: FenField::Board, offset: 0, color: Color::White }),
+            Position::try_from_fen(Ruleset::Standard, fen),
+        );
+
+        // but black still needs one
+        assert_eq!(
+            Err(FenError::MissingKing { field: FenField::Board, offset: 0, color: Color::Black }),
+            Position::try_from_fen(Ruleset::Horde, b"8/8/8/8/8/8/8/PPPPPPPP w - - 0 1"),
+        );
+    }
+
+    #[test]
+    fn try_from_fen_invalid_pocket() {
+        // pockets only exist in crazyhouse
+        assert_eq!(
+            Err(FenError::InvalidPocket { field: FenField::Board, offset: 19 }),
+            Position::try_from_fen(Ruleset::Standard, b"4k3/8/8/8/8/8/8/4K3[] w - - 0 1"),
+        );
+
+        assert_eq!(
+            Err(FenError::InvalidPocket { field: FenField::Board, offset: 19 }),
+            Position::try_from_fen(Ruleset::Crazyhouse, b"4k3/8/8/8/8/8/8/4K3[Qn w - - 0 1"),
+        );
+
+        // kings can never be captured, so they're never in a pocket
+        assert_eq!(
+            Err(FenError::InvalidPiece { field: FenField::Board, offset: 20, byte: b'K' }),
+            Position::try_from_fen(Ruleset::Crazyhouse, b"4k3/8/8/8/8/8/8/4K3[K] w - - 0 1"),
+        );
+
+        // only pieces a pawn can promote to may be marked as promoted
+        assert_eq!(
+            Err(FenError::InvalidPiece { field: FenField::Board, offset: 18, byte: b'~' }),
+            Position::try_from_fen(Ruleset::Crazyhouse, b"4k3/8/8/8/8/8/8/4K~3[] w - - 0 1"),
+        );
+    }
+
+    #[test]
+    fn try_from_fen_rank_overflow() {
+        assert_eq!(
+            Err(FenError::RankOverflow { field: FenField::Board, offset: 8 }),
+            Position::try_from_fen(Ruleset::Standard, b"rnbqkbnrr/8/8/8/8/8/8/4K3 w - - 0 1"),
+        );
+
+        assert_eq!(
+            Err(FenError::RankOverflow { field: FenField::Board, offset: 5 }),
+            Position::try_from_fen(Ruleset::Standard, b"4k3/p8/8/8/8/8/8/4K3 w - - 0 1"),
+        );
+
+        assert_eq!(
+            Err(FenError::RankOverflow { field: FenField::Board, offset: 17 }),
+            Position::try_from_fen(Ruleset::Standard, b"4k3/8/8/8/8/8/8/8/4K3 w - - 0 1"),
+        );
+    }
+
+    #[test]
+    fn try_from_fen_short_rank() {
+        assert_eq!(
+            Err(FenError::RankOverflow { field: FenField::Board, offset: 4 }),
+            Position::try_from_fen(Ruleset::Standard, b"4k2/8/8/8/8/8/8/4K3 w - - 0 1"),
+        );
+
+        assert_eq!(
+            Err(FenError::RankOverflow { field: FenField::Board, offset: 19 }),
+            Position::try_from_fen(Ruleset::Standard, b"4k3/8/8/8/8/8/8/4K2 w - - 0 1"),
+        );
+
+        assert_eq!(
+            Err(FenError::RankOverflow { field: FenField::Board, offset: 17 }),
+            Position::try_from_fen(Ruleset::Standard, b"4k3/8/8/8/8/8/4K3 w - - 0 1"),
+        );
+    }
+
+    #[test]
+    fn try_from_fen_trailing_fields() {
+        assert_eq!(
+            Err(FenError::InvalidClock { field: FenField::Fullmoves, offset: 30 }),
+            Position::try_from_fen(Ruleset::Standard, b"4k3/8/8/8/8/8/8/4K3 w - - 0 1 x"),
+        );
+
+        assert_eq!(
+            Err(FenError::InvalidChecks { field: FenField::Checks, offset: 35 }),
+            Position::try_from_fen(Ruleset::ThreeCheck, b"4k3/8/8/8/8/8/8/4K3 w - - 0 1 +0+0 x"),
+        );
+    }
+
+    #[test]
+    fn try_from_fen_missing_king() {
+        assert_eq!(
+            Err(FenError::MissingKing { field: FenField::Board, offset: 0, color: Color::Black }),
+            Position::try_from_fen(Ruleset::Standard, b"8/8/8/8/8/8/8/4K3 w - - 0 1"),
+        );
+    }
+
+    #[test]
+    fn try_from_fen_invalid_turn() {
+        assert_eq!(
+            Err(FenError::InvalidTurn { field: FenField::Turn, offset: 20 }),
+            Position::try_from_fen(Ruleset::Standard, b"4k3/8/8/8/8/8/8/4K3 x - - 0 1"),
+        );
+
+        assert_eq!(
+            Err(FenError::InvalidTurn { field: FenField::Turn, offset: 19 }),
+            Position::try_from_fen(Ruleset::Standard, b"4k3/8/8/8/8/8/8/4K3"),
+        );
+    }
+
+    #[test]
+    fn try_from_fen_invalid_castling() {
+        assert_eq!(
+            Err(FenError::InvalidCastling { field: FenField::Castling, offset: 27, byte: b'x' }),
+            Position::try_from_fen(Ruleset::Standard, b"r3k2r/8/8/8/8/8/8/R3K2R w Kx - 0 1"),
+        );
+
+        // a right may not be given twice, even with a different letter
+        assert_eq!(
+            Err(FenError::InvalidCastling { field: FenField::Castling, offset: 27, byte: b'K' }),
+            Position::try_from_fen(Ruleset::Standard, b"r3k2r/8/8/8/8/8/8/R3K2R w KKqq - 0 1"),
+        );
+
+        assert_eq!(
+            Err(FenError::InvalidCastling { field: FenField::Castling, offset: 27, byte: b'H' }),
+            Position::try_from_fen(Ruleset::Chess960, b"r3k2r/8/8/8/8/8/8/R3K2R w KHkq - 0 1"),
+        );
+    }
+
+    #[test]
+    fn try_from_fen_castling_without_rook() {
+        assert_eq!(
+            Err(FenError::CastlingWithoutRook { field: FenField::Castling, offset: 26, byte: b'Q' }),
+            Position::try_from_fen(Ruleset::Standard, b"r3k2r/8/8/8/8/8/8/4K2R w KQkq - 0 1"),
+        );
+
+        assert_eq!(
+            Err(FenError::CastlingWithoutRook { field: FenField::Castling, offset: 26, byte: b'G' }),
+            Position::try_from_fen(Ruleset::Chess960, b"r3k2r/8/8/8/8/8/8/R3K2R w Gq - 0 1"),
+        );
+    }
+
+    #[test]
+    fn try_from_fen_invalid_en_passant() {
+        // wrong rank for the side to move
+        assert_eq!(
+            Err(FenError::InvalidEnPassant { field: FenField::EnPassant, offset: 27 }),
+            Position::try_from_fen(Ruleset::Standard, b"4k3/8/8/3pP3/8/8/8/4K3 w - d3 0 1"),
+        );
+
+        // no pawn could have just jumped over the square
+        assert_eq!(
+            Err(FenError::InvalidEnPassant { field: FenField::EnPassant, offset: 26 }),
+            Position::try_from_fen(Ruleset::Standard, b"4k3/8/8/4P3/8/8/8/4K3 w - d6 0 1"),
+        );
+
+        assert_eq!(
+            Err(FenError::InvalidEnPassant { field: FenField::EnPassant, offset: 27 }),
+            Position::try_from_fen(Ruleset::Standard, b"4k3/8/8/3pP3/8/8/8/4K3 w - d66 0 1"),
+        );
+    }
+
+    #[test]
+    fn try_from_fen_invalid_clock() {
+        assert_eq!(
+            Err(FenError::InvalidClock { field: FenField::Halfmoves, offset: 26 }),
+            Position::try_from_fen(Ruleset::Standard, b"4k3/8/8/8/8/8/8/4K3 w - - x 1"),
+        );
+
+        assert_eq!(
+            Err(FenError::InvalidClock { field: FenField::Fullmoves, offset: 28 }),
+            Position::try_from_fen(Ruleset::Standard, b"4k3/8/8/8/8/8/8/4K3 w - - 0 -1"),
+        );
+
+        assert_eq!(
+            Err(FenError::InvalidClock { field: FenField::Halfmoves, offset: 26 }),
+            Position::try_from_fen(Ruleset::Standard, b"4k3/8/8/8/8/8/8/4K3 w - - 4294967296 1"),
+        );
+    }
+
+    #[test]
+    fn try_from_fen_long_halfmove_clock() {
+        let position = Position::try_from_fen(Ruleset::Standard,
+            b"4k3/8/8/8/8/8/8/4K2R w - - 256 200").unwrap();
+
+        assert_eq!(255, position.halfmoves());
+    }
+
+    #[test]
+    fn try_from_fen_fullmove_overflow() {
+        let position = Position::try_from_fen(Ruleset::Standard,
+            b"4k3/8/8/8/8/8/8/4K3 b - - 0 32768").unwrap();
+
+        assert_eq!(u16::MAX, position.ply());
+
+        // one move later the ply no longer fits
+        assert_eq!(
+            Err(FenError::InvalidClock { field: FenField::Fullmoves, offset: 28 }),
+            Position::try_from_fen(Ruleset::Standard, b"4k3/8/8/8/8/8/8/4K3 w - - 0 32769"),
+        );
+
+        // even when the three-check counters follow it
+        assert_eq!(
+            Err(FenError::InvalidClock { field: FenField::Fullmoves, offset: 28 }),
+            Position::try_from_fen(Ruleset::ThreeCheck, b"4k3/8/8/8/8/8/8/4K3 w - - 0 32769 +0+0"),
+        );
+    }
+
+    #[test]
+    fn try_from_fen_inferring_chess960() {
+        let fen = b"bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9";
+
+        assert_eq!(Ruleset::Standard, Position::try_from_fen(Ruleset::Standard, fen).unwrap().ruleset());
+        assert_eq!(Ruleset::Chess960, Position::try_from_fen_inferring_chess960(Ruleset::Standard, fen).unwrap().ruleset());
+
+        let fen = b"rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+        assert_eq!(Ruleset::Standard, Position::try_from_fen_inferring_chess960(Ruleset::Standard, fen).unwrap().ruleset());
+    }
+
+    #[test]
+    fn try_from_fen_inferring_chess960_variants() {
+        // other variants keep their ruleset, whatever their castling field
+        for (ruleset, fen) in [
+            (Ruleset::Crazyhouse, "r3k2r/8/8/8/8/8/8/R3K2R[] w HAha - 0 1"),
+            (Ruleset::ThreeCheck, "r3k2r/8/8/8/8/8/8/R3K2R w HAha - 0 1 +0+0"),
+            (Ruleset::Horde,      "r3k2r/8/8/8/8/8/PPPPPPPP/PPPPPPPP w ha - 0 1"),
+        ] {
+            let position = Position::try_from_fen_inferring_chess960(ruleset, fen.as_bytes()).unwrap();
+
+            assert_eq!(ruleset, position.ruleset(), "{fen}");
+        }
+    }
+
+    #[test]
+    fn fen_error_display() {
+        assert_eq!(
+            "invalid piece 'x' in piece placement at byte 10",
+            FenError::InvalidPiece { field: FenField::Board, offset: 10, byte: b'x' }.to_string(),
+        );
+    }
 }
@@ -0,0 +1,170 @@
+use stockfish_core::prelude::*;
+
+use core::fmt::{Display, Formatter, Result as FmtResult};
+
+/// The fields of a FEN string, in the order they appear.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum FenField {
+    /// Piece placement, from rank 8 down to rank 1, followed by the pieces in
+    /// each side's pocket in crazyhouse.
+    Board,
+
+    /// The side to move.
+    Turn,
+
+    /// Castling availability.
+    Castling,
+
+    /// The en passant target square.
+    EnPassant,
+
+    /// The number of halfmoves since the last capture or pawn advance.
+    Halfmoves,
+
+    /// The number of the full move.
+    Fullmoves,
+
+    /// The number of checks given by each side in three-check.
+    Checks,
+}
+
+/// An error encountered while parsing a FEN string with
+/// [`Position::try_from_fen`](crate::position::Position::try_from_fen).
+///
+/// Every error records the field it was found in, and the byte offset into the
+/// entire FEN string where the offending input begins.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum FenError {
+    /// A byte that doesn't identify any piece was found on the board.
+    InvalidPiece { field: FenField, offset: usize, byte: u8 },
+
+    /// A rank didn't describe exactly eight squares, or the board didn't
+    /// describe exactly eight ranks.
+    RankOverflow { field: FenField, offset: usize },
+
+    /// The pocket following the board is unterminated, holds more of a piece
+    /// than could ever have been captured, or was given for a ruleset other
+    /// than crazyhouse.
+    InvalidPocket { field: FenField, offset: usize },
+
+    /// One of the sides has no king on the board.
+    MissingKing { field: FenField, offset: usize, color: Color },
+
+    /// The side to move is missing or isn't one of `w` or `b`.
+    InvalidTurn { field: FenField, offset: usize },
+
+    /// A byte that isn't a castling letter, or a letter for a castling right
+    /// that was already given, was found in the castling field. A missing
+    /// castling field is reported as a space.
+    InvalidCastling { field: FenField, offset: usize, byte: u8 },
+
+    /// A castling letter was found with no king and rook on the home rank to
+    /// castle with.
+    CastlingWithoutRook { field: FenField, offset: usize, byte: u8 },
+
+    /// The en passant square is missing or malformed, or couldn't possibly
+    /// have resulted from the last move made.
+    InvalidEnPassant { field: FenField, offset: usize },
+
+    /// The halfmove clock or fullmove number isn't a number, or is too large.
+    /// Outside of three-check, this is also reported for anything following
+    /// the fullmove number.
+    InvalidClock { field: FenField, offset: usize },
+
+    /// The checks given in three-check aren't written as `+N+M`, one side has
+    /// given more than three, or something follows them.
+    InvalidChecks { field: FenField, offset: usize },
+}
+
+impl FenError {
+    /// Returns the FEN field in which the error was found.
+    #[inline]
+    #[must_use]
+    pub const fn field(self) -> FenField {
+        match self {
+            Self::InvalidPiece        { field, .. } |
+            Self::RankOverflow        { field, .. } |
+            Self::InvalidPocket       { field, .. } |
+            Self::MissingKing         { field, .. } |
+            Self::InvalidTurn         { field, .. } |
+            Self::InvalidCastling     { field, .. } |
+            Self::CastlingWithoutRook { field, .. } |
+            Self::InvalidEnPassant    { field, .. } |
+            Self::InvalidClock        { field, .. } |
+            Self::InvalidChecks       { field, .. } => field,
+        }
+    }
+
+    /// Returns the byte offset into the FEN string at which the error was
+    /// found.
+    #[inline]
+    #[must_use]
+    pub const fn offset(self) -> usize {
+        match self {
+            Self::InvalidPiece        { offset, .. } |
+            Self::RankOverflow        { offset, .. } |
+            Self::InvalidPocket       { offset, .. } |
+            Self::MissingKing         { offset, .. } |
+            Self::InvalidTurn         { offset, .. } |
+            Self::InvalidCastling     { offset, .. } |
+            Self::CastlingWithoutRook { offset, .. } |
+            Self::InvalidEnPassant    { offset, .. } |
+            Self::InvalidClock        { offset, .. } |
+            Self::InvalidChecks       { offset, .. } => offset,
+        }
+    }
+}
+
+impl Display for FenField {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.write_str(match *self {
+            Self::Board     => "piece placement",
+            Self::Turn      => "side to move",
+            Self::Castling  => "castling availability",
+            Self::EnPassant => "en passant square",
+            Self::Halfmoves => "halfmove clock",
+            Self::Fullmoves => "fullmove number",
+            Self::Checks    => "checks given",
+        })
+    }
+}
+
+impl Display for FenError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match *self {
+            Self::InvalidPiece { byte, .. } =>
+                write!(f, "invalid piece '{}'", char::from(byte))?,
+
+            Self::RankOverflow { .. } =>
+                f.write_str("expected eight squares per rank and eight ranks")?,
+
+            Self::InvalidPocket { .. } =>
+                f.write_str("invalid pocket")?,
+
+            Self::MissingKing { color, .. } =>
+                write!(f, "no {} king", if color.is_white() { "white" } else { "black" })?,
+
+            Self::InvalidTurn { .. } =>
+                f.write_str("expected 'w' or 'b'")?,
+
+            Self::InvalidCastling { byte, .. } =>
+                write!(f, "invalid castling letter '{}'", char::from(byte))?,
+
+            Self::CastlingWithoutRook { byte, .. } =>
+                write!(f, "no rook to castle with for '{}'", char::from(byte))?,
+
+            Self::InvalidEnPassant { .. } =>
+                f.write_str("impossible en passant square")?,
+
+            Self::InvalidClock { .. } =>
+                f.write_str("expected a number")?,
+
+            Self::InvalidChecks { .. } =>
+                f.write_str("expected '+N+M' with at most three checks each")?,
+        }
+
+        write!(f, " in {} at byte {}", self.field(), self.offset())
+    }
+}
+
+impl std::error::Error for FenError {}
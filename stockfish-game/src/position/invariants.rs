@@ -0,0 +1,369 @@
+use crate::prelude::*;
+use stockfish_core::prelude::*;
+
+use core::fmt::{Display, Formatter, Result as FmtResult};
+
+/// An internal invariant of a [`Position`] found to be violated by
+/// [`Position::validate_invariants`].
+///
+/// A [`Position`] keeps several redundant views of the same board so that
+/// each can be queried quickly. Any of these disagreeing with the others is a
+/// bug in whatever last modified the position.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Invariant {
+    /// The bitboard of every occupied square disagrees with the board.
+    Occupancy,
+
+    /// The bitboard for a color disagrees with the board.
+    ColorBitboard(Color),
+
+    /// The bitboard for a token disagrees with the board.
+    TokenBitboard(Token),
+
+    /// The number of pieces of a color disagrees with the board.
+    ColorCount(Color),
+
+    /// The number of a specific piece disagrees with the board.
+    PieceCount(Piece),
+
+    /// The castling rights forfeited by moving to or from a square disagree
+    /// with the castling paths.
+    CastlingSquare(Square),
+
+    /// A castling right is held without a king and rook on the origin squares
+    /// of its castling path.
+    CastlingRights(CastlingVariety),
+
+    /// A piece marked as promoted isn't a knight, bishop, rook, or queen, or
+    /// is marked outside of crazyhouse.
+    Promoted(Square),
+
+    /// The en passant square isn't behind an enemy pawn that could have just
+    /// advanced two squares, or there's no pawn able to capture onto it.
+    EnPassant(Square),
+
+    /// The incrementally-updated position key disagrees with the board.
+    PositionKey,
+
+    /// The incrementally-updated pawn key disagrees with the board.
+    PawnKey,
+
+    /// The incrementally-updated material key disagrees with the board.
+    MaterialKey,
+
+    /// The incrementally-updated non-pawn key for a color disagrees with the
+    /// board.
+    NonPawnKey(Color),
+}
+
+#[allow(clippy::multiple_inherent_impl)]
+impl Position {
+    /// Cross-checks every redundant piece of state kept by the position,
+    /// returning the first [`Invariant`] found to be violated.
+    ///
+    /// This is called automatically in debug builds whenever the board is
+    /// modified, so a violation will be caught as close as possible to the
+    /// code responsible for it.
+    pub fn validate_invariants(&self) -> Result<(), Invariant> {
+        self.validate_board()?;
+        self.validate_castling()?;
+        self.validate_promoted()?;
+        self.validate_en_passant()?;
+        self.validate_keys()
+    }
+
+    /// Validates the subset of invariants which must hold after every piece
+    /// is placed on or removed from the board, even in the middle of making
+    /// or unmaking a move.
+    pub(crate) fn validate_board_invariants(&self) -> Result<(), Invariant> {
+        self.validate_board()?;
+        self.validate_keys()
+    }
+
+    fn validate_board(&self) -> Result<(), Invariant> {
+        let mut occupancy = Bitboard::EMPTY;
+        let mut by_color  = [Bitboard::EMPTY; Color::COUNT];
+        let mut by_token  = [Bitboard::EMPTY; Token::COUNT];
+        let mut by_piece  = [0_u8; Piece::COUNT];
+
+        for (square, piece) in self.board.iter() {
+            occupancy               |= square;
+            by_color[piece.color()] |= square;
+            by_token[piece.token()] |= square;
+            by_piece[piece]         += 1;
+        }
+
+        if self.bb_all != occupancy {
+            return Err(Invariant::Occupancy);
+        }
+
+        if let Some(color) = Color::iter().find(|&c| self.bb_by_color[c] != by_color[c]) {
+            return Err(Invariant::ColorBitboard(color));
+        }
+
+        if let Some(token) = Token::iter().find(|&t| self.bb_by_piece[t] != by_token[t]) {
+            return Err(Invariant::TokenBitboard(token));
+        }
+
+        if let Some(color) = Color::iter().find(|&c| usize::from(self.count_by_color[c]) != by_color[c].count()) {
+            return Err(Invariant::ColorCount(color));
+        }
+
+        if let Some(piece) = Piece::iter().find(|&p| self.count_by_token[p] != by_piece[p]) {
+            return Err(Invariant::PieceCount(piece));
+        }
+
+        Ok(())
+    }
+
+    fn validate_castling(&self) -> Result<(), Invariant> {
+        let mut by_square = [CastlingRights::NONE; Square::COUNT];
+
+        for path in self.castling_paths.iter().flatten() {
+            by_square[path.king_origin()] |= path.rights();
+            by_square[path.rook_origin()] |= path.rights();
+        }
+
+        if let Some(square) = Square::iter().find(|&s| self.castling_by_square[s] != by_square[s]) {
+            return Err(Invariant::CastlingSquare(square));
+        }
+
+        for variety in CastlingVariety::iter() {
+            if self.can_castle(variety.rights()) && !self.has_castling_pieces(variety) {
+                return Err(Invariant::CastlingRights(variety));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns [`true`] if the king and rook of the castling path for
+    /// `variety` are both still on their origin squares.
+    pub(super) fn has_castling_pieces(&self, variety: CastlingVariety) -> bool {
+        let color = variety.color();
+
+        self.castling_paths[variety].map_or(false, |path| {
+            self.board[path.king_origin()] == Some(color | Token::King) &&
+            self.board[path.rook_origin()] == Some(color | Token::Rook)
+        })
+    }
+
+    fn validate_promoted(&self) -> Result<(), Invariant> {
+        let promotable = if self.ruleset == Ruleset::Crazyhouse {
+            self.bb_all & !(self.bb_by_piece[Token::Pawn] | self.bb_by_piece[Token::King])
+        } else {
+            Bitboard::EMPTY
+        };
+
+        if let Some(square) = Option::<Square>::from(self.promoted & !promotable) {
+            return Err(Invariant::Promoted(square));
+        }
+
+        Ok(())
+    }
+
+    fn validate_en_passant(&self) -> Result<(), Invariant> {
+        let Some(square) = self.state.en_passant else { return Ok(()) };
+
+        let us   = self.turn;
+        let them = !us;
+
+        // one of our pawns must also be able to capture onto the square
+        let valid = self.is_double_push_square(square)
+            && (them | Token::Pawn).attacks(square, self.bb_all)
+                .overlaps(self.bitboard_for_token(us | Token::Pawn));
+
+        if !valid {
+            return Err(Invariant::EnPassant(square));
+        }
+
+        Ok(())
+    }
+
+    /// Returns [`true`] if the enemy pawn in front of `square` could have just
+    /// passed over it from its starting square.
+    pub(super) fn is_double_push_square(&self, square: Square) -> bool {
+        let them = !self.turn;
+
+        square.from_perspective(self.turn).rank() == Rank::_6
+            && self.board[square].is_none()
+            && self.board[square.wrapping_sub(them.direction())].is_none()
+            && self.board[square.wrapping_add(them.direction())] == Some(them | Token::Pawn)
+    }
+
+    fn validate_keys(&self) -> Result<(), Invariant> {
+        let keys = self.compute_keys();
+
+        if keys.position != self.state.keys.position {
+            return Err(Invariant::PositionKey);
+        }
+
+        if keys.pawns != self.state.keys.pawns {
+            return Err(Invariant::PawnKey);
+        }
+
+        if keys.material != self.state.keys.material {
+            return Err(Invariant::MaterialKey);
+        }
+
+        if let Some(color) = Color::iter().find(|&c| keys.non_pawns[c] != self.state.keys.non_pawns[c]) {
+            return Err(Invariant::NonPawnKey(color));
+        }
+
+        Ok(())
+    }
+}
+
+impl Display for Invariant {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match *self {
+            Self::Occupancy =>
+                f.write_str("occupancy bitboard disagrees with the board"),
+
+            Self::ColorBitboard(color) =>
+                write!(f, "{color:?} bitboard disagrees with the board"),
+
+            Self::TokenBitboard(token) =>
+                write!(f, "{token:?} bitboard disagrees with the board"),
+
+            Self::ColorCount(color) =>
+                write!(f, "{color:?} piece count disagrees with the board"),
+
+            Self::PieceCount(piece) =>
+                write!(f, "{piece:?} count disagrees with the board"),
+
+            Self::CastlingSquare(square) =>
+                write!(f, "castling rights for {square:?} disagree with the castling paths"),
+
+            Self::CastlingRights(variety) =>
+                write!(f, "{variety:?} castling right held without a king and rook to castle"),
+
+            Self::Promoted(square) =>
+                write!(f, "piece on {square:?} can't have been promoted"),
+
+            Self::EnPassant(square) =>
+                write!(f, "impossible en passant square {square:?}"),
+
+            Self::PositionKey =>
+                f.write_str("position key disagrees with the board"),
+
+            Self::PawnKey =>
+                f.write_str("pawn key disagrees with the board"),
+
+            Self::MaterialKey =>
+                f.write_str("material key disagrees with the board"),
+
+            Self::NonPawnKey(color) =>
+                write!(f, "{color:?} non-pawn key disagrees with the board"),
+        }
+    }
+}
+
+impl std::error::Error for Invariant {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validate_invariants() {
+        for fen in [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3",
+        ] {
+            let position = Position::from_fen(Ruleset::Standard, fen.as_bytes());
+
+            assert_eq!(Ok(()), position.validate_invariants(), "{fen}");
+        }
+
+        assert_eq!(Ok(()), Position::empty(Ruleset::Standard).validate_invariants());
+    }
+
+    #[test]
+    fn validate_invariants_board() {
+        let mut position = Position::from_fen(Ruleset::Standard,
+            b"4k3/8/8/8/8/8/8/4K2R w K - 0 1");
+
+        position.board[Square::A1] = Some(Piece::WhiteRook);
+        assert_eq!(Err(Invariant::Occupancy), position.validate_invariants());
+
+        position.bb_all |= Square::A1;
+        assert_eq!(Err(Invariant::ColorBitboard(Color::White)), position.validate_invariants());
+
+        position.bb_by_color[Color::White] |= Square::A1;
+        assert_eq!(Err(Invariant::TokenBitboard(Token::Rook)), position.validate_invariants());
+
+        position.bb_by_piece[Token::Rook] |= Square::A1;
+        assert_eq!(Err(Invariant::ColorCount(Color::White)), position.validate_invariants());
+
+        position.count_by_color[Color::White] += 1;
+        assert_eq!(Err(Invariant::PieceCount(Piece::WhiteRook)), position.validate_invariants());
+
+        position.count_by_token[Piece::WhiteRook] += 1;
+        assert_eq!(Err(Invariant::PositionKey), position.validate_invariants());
+    }
+
+    #[test]
+    fn validate_invariants_castling() {
+        let mut position = Position::from_fen(Ruleset::Standard,
+            b"4k3/8/8/8/8/8/8/4K2R w K - 0 1");
+
+        position.castling_by_square[Square::A1] = CastlingRights::WHITE_OOO;
+        assert_eq!(Err(Invariant::CastlingSquare(Square::A1)), position.validate_invariants());
+
+        position.castling_by_square[Square::A1] = CastlingRights::NONE;
+        position.state.castling_rights         |= CastlingRights::WHITE_OOO;
+        assert_eq!(Err(Invariant::CastlingRights(CastlingVariety::WhiteQueenside)), position.validate_invariants());
+
+        let mut position = Position::from_fen(Ruleset::Standard,
+            b"4k3/8/8/8/8/8/8/4K2R w K - 0 1");
+        let _            = position.remove(Square::H1);
+
+        assert_eq!(Err(Invariant::CastlingRights(CastlingVariety::WhiteKingside)), position.validate_invariants());
+    }
+
+    #[test]
+    fn validate_invariants_promoted() {
+        let mut position = Position::from_fen(Ruleset::Crazyhouse,
+            b"4k3/8/8/8/8/8/8/4K2Q w - - 0 1");
+
+        position.promoted |= Square::H1;
+        assert_eq!(Ok(()), position.validate_invariants());
+
+        position.promoted |= Square::E1;
+        assert_eq!(Err(Invariant::Promoted(Square::E1)), position.validate_invariants());
+
+        let mut position = Position::from_fen(Ruleset::Standard,
+            b"4k3/8/8/8/8/8/8/4K2Q w - - 0 1");
+
+        position.promoted |= Square::H1;
+        assert_eq!(Err(Invariant::Promoted(Square::H1)), position.validate_invariants());
+    }
+
+    #[test]
+    fn validate_invariants_en_passant() {
+        let mut position = Position::from_fen(Ruleset::Standard,
+            b"4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1");
+
+        assert_eq!(Ok(()), position.validate_invariants());
+
+        position.state.en_passant = Some(Square::C6);
+        assert_eq!(Err(Invariant::EnPassant(Square::C6)), position.validate_invariants());
+    }
+
+    #[test]
+    fn validate_invariants_keys() {
+        let mut position = Position::from_fen(Ruleset::Standard,
+            b"4k3/8/8/8/8/8/8/4K2R w K - 0 1");
+
+        position.state.keys.material = position.state.keys.pawns;
+        assert_eq!(Err(Invariant::MaterialKey), position.validate_invariants());
+    }
+
+    #[test]
+    fn invariant_display() {
+        assert_eq!("impossible en passant square C6",
+            Invariant::EnPassant(Square::C6).to_string());
+    }
+}
@@ -0,0 +1,574 @@
+use crate::prelude::*;
+use stockfish_core::prelude::*;
+use stockfish_core::accelerate;
+
+#[allow(clippy::multiple_inherent_impl)]
+impl Position {
+    /// Generates every legal move for the side to move. There are none once
+    /// the game has been won under the rules of the variant being played, as
+    /// reported by [`Position::variant_outcome`].
+    pub fn legal_moves(&self) -> MoveList {
+        // once the game has been won on the board, there's nothing left to
+        // play
+        if self.variant_outcome().is_some() {
+            return MoveList::new();
+        }
+
+        // captures are compulsory in antichess, but with no king to protect,
+        // every pseudo-legal move is otherwise legal
+        if self.ruleset == Ruleset::Antichess {
+            let captures = self.generate(GenType::Captures);
+
+            return if captures.is_empty() { self.generate(GenType::NonEvasions) } else { captures };
+        }
+
+        let us     = self.turn;
+        let king   = self.king_square(us);
+        let pinned = self.state.blockers[us] & self.bitboard_for_color(us);
+
+        let mut moves = self.generate(if self.state.checkers.is_any() {
+            GenType::Evasions
+        } else {
+            GenType::NonEvasions
+        });
+
+        // only moves by pinned pieces, moves by the king, and en passant
+        // captures (which remove two pieces from the board at once) are able to
+        // leave the king in check after evasions have been accounted for; in
+        // atomic chess any move might, and in racing kings any move might
+        // illegally give check
+        moves.retain(|mv| {
+            let origin = mv.origin();
+
+            let suspect =
+                self.ruleset == Ruleset::Atomic      ||
+                self.ruleset == Ruleset::RacingKings ||
+                pinned.contains(origin)              ||
+                king == Some(origin)                 ||
+                mv.move_type() == MoveType::EnPassant;
+
+            !suspect || self.is_legal_unchecked(mv)
+        });
+
+        moves
+    }
+
+    /// Returns [`true`] if `mv` is legal in this position.
+    ///
+    /// Any move may be passed, including ones from untrusted sources like a
+    /// transposition table or an opening book, which may have been recorded
+    /// in a different position altogether.
+    #[must_use]
+    pub fn is_legal(&self, mv: Move) -> bool {
+        self.variant_outcome().is_none() && self.is_pseudo_legal(mv) && self.is_legal_unchecked(mv)
+    }
+
+    /// Returns [`true`] if `mv` obeys the movement rules of the piece being
+    /// moved, but may leave the moving side's king in check. In other words,
+    /// returns [`true`] if `mv` would have been produced by
+    /// [`Position::generate`] with [`GenType::Evasions`] or
+    /// [`GenType::NonEvasions`], as appropriate, without having to generate
+    /// every move.
+    #[must_use]
+    pub fn is_pseudo_legal(&self, mv: Move) -> bool {
+        let us          = self.turn;
+        let origin      = mv.origin();
+        let destination = mv.destination();
+        let checkers    = self.state.checkers;
+
+        if mv.move_type() == MoveType::Drop {
+            return self.is_pseudo_legal_drop(mv);
+        }
+
+        let Some(piece) = self[origin] else { return false };
+
+        if piece.color() != us {
+            return false;
+        }
+
+        // a king would be caught in the explosion of its own capture
+        if self.ruleset == Ruleset::Atomic
+            && piece.token() == Token::King
+            && self.bitboard_for_color(!us).contains(destination)
+        {
+            return false;
+        }
+
+        if mv.move_type() == MoveType::Castling {
+            return piece.token() == Token::King
+                && checkers.is_empty()
+                && self.is_pseudo_legal_castling(origin, destination);
+        }
+
+        if self.bitboard_for_color(us).contains(destination) {
+            return false;
+        }
+
+        let movable = match piece.token() {
+            Token::Pawn => self.is_pseudo_legal_pawn_move(mv),
+            _           => mv.move_type() == MoveType::Normal
+                && piece.attacks(origin, self.bitboard()).contains(destination),
+        };
+
+        if !movable {
+            return false;
+        }
+
+        // the king may always try to step out of check, but in double check
+        // that's all that can be done, and otherwise every other piece has to
+        // capture the checker or block the check; in atomic chess, a capture
+        // anywhere nearby might blow up the checker or the enemy king instead
+        if checkers.is_empty() || piece.token() == Token::King || self.ruleset == Ruleset::Atomic {
+            return true;
+        }
+
+        if checkers.is_many() {
+            return false;
+        }
+
+        let Some(checker) = Option::<Square>::from(checkers) else {
+            return false;
+        };
+
+        let captured = match mv.move_type() {
+            MoveType::EnPassant => destination.wrapping_sub(us.direction()),
+            _                   => destination,
+        };
+
+        captured == checker || self.king_square(us).map_or(false, |king| {
+            accelerate::between(king, checker).contains(destination)
+        })
+    }
+
+    /// Returns [`true`] if a pseudo-legal move does not leave the moving side's
+    /// king in check. In antichess, where there is no check, a move is instead
+    /// legal if it captures or if there's no capture to be made. In racing
+    /// kings, a move also may not give check.
+    ///
+    /// The move *must* be pseudo-legal in this position. Passing any other move
+    /// may produce nonsensical results. Use [`Position::is_legal`] for moves
+    /// that haven't been generated for this position.
+    #[must_use]
+    pub(crate) fn is_legal_unchecked(&self, mv: Move) -> bool {
+        let us          = self.turn;
+        let them        = !us;
+        let origin      = mv.origin();
+        let destination = mv.destination();
+
+        // a dropped piece can only ever block an attack on the king, never
+        // expose it to one
+        if mv.move_type() == MoveType::Drop {
+            return true;
+        }
+
+        if self.ruleset == Ruleset::Atomic {
+            return self.is_legal_atomic(mv);
+        }
+
+        if self.ruleset == Ruleset::Antichess {
+            return self.is_capture(mv) || self.generate(GenType::Captures).is_empty();
+        }
+
+        debug_assert_eq!(Some(us), self[origin].map(Piece::color),
+            "the moving piece must belong to the side to move");
+
+        if self.ruleset == Ruleset::RacingKings && self.gives_check(mv) {
+            return false;
+        }
+
+        // without a king, there's nothing to be left in check
+        let Some(king) = self.king_square(us) else { return true };
+
+        match mv.move_type() {
+            // en passant captures are a tricky special case since they vacate
+            // two squares at once, possibly exposing the king along its rank;
+            // they're rare enough that we simply test whether the king would
+            // be attacked after the move is made
+            MoveType::EnPassant => {
+                let captured  = destination.wrapping_sub(us.direction());
+                let occupancy = (self.bitboard() ^ origin ^ captured) | destination;
+
+                let rooks   = self.bitboard_for_piece(Token::Rook)   | self.bitboard_for_piece(Token::Queen);
+                let bishops = self.bitboard_for_piece(Token::Bishop) | self.bitboard_for_piece(Token::Queen);
+                let enemies = self.bitboard_for_color(them);
+
+                (Token::Rook  .attacks(king, occupancy) & rooks   & enemies).is_empty() &&
+                (Token::Bishop.attacks(king, occupancy) & bishops & enemies).is_empty()
+            },
+
+            // move generation doesn't check whether the king passes through
+            // an attacked square while castling, so we do it here; since we
+            // never generate castling while in check, we don't need to check
+            // the king's origin square
+            MoveType::Castling => {
+                let Some(side) = CastlingSide::new(origin.file(), destination.file()) else {
+                    return false;
+                };
+
+                let variety = CastlingVariety::new(us, side);
+                let path    = accelerate::between(origin, variety.king_destination());
+                let enemies = self.bitboard_for_color(them);
+
+                // in Chess960 the castling rook itself might be the only thing
+                // shielding the king from an enemy slider on the home rank
+                path.iter().all(|square| {
+                    (self.attackers_to(square, self.bitboard()) & enemies).is_empty()
+                }) && self.state.blockers[us].omits(destination)
+            },
+
+            // the king may not move onto an attacked square, taking care to
+            // account for squares that it's currently blocking from a slider
+            _ if origin == king => (
+                self.attackers_to(destination, self.bitboard() ^ origin)
+                    & self.bitboard_for_color(them)
+            ).is_empty(),
+
+            // any other piece may move freely unless it's pinned, in which
+            // case it may only move along the line of the pin
+            _ => self.state.blockers[us].omits(origin)
+                || accelerate::line(origin, destination).contains(king),
+        }
+    }
+
+    /// Returns [`true`] if the king on `origin` may castle with the rook on
+    /// `rook`, ignoring whether or not it passes through check.
+    fn is_pseudo_legal_castling(&self, origin: Square, rook: Square) -> bool {
+        let Some(side) = CastlingSide::new(origin.file(), rook.file()) else {
+            return false;
+        };
+
+        self.castling_path(CastlingVariety::new(self.turn, side)).map_or(false, |path| {
+            path.king_origin() == origin
+                && path.rook_origin() == rook
+                && self.can_castle(path.rights())
+                && !self.is_castling_impeded(path)
+        })
+    }
+
+    /// Returns [`true`] if the drop `mv` places a piece from the side to
+    /// move's pocket onto an empty square, blocking the check if in check.
+    fn is_pseudo_legal_drop(&self, mv: Move) -> bool {
+        let us          = self.turn;
+        let destination = mv.destination();
+        let checkers    = self.state.checkers;
+
+        if self.ruleset != Ruleset::Crazyhouse
+            || self.pockets[us | mv.dropped()] == 0
+            || self.bitboard().contains(destination)
+        {
+            return false;
+        }
+
+        // drops can't capture, so the only way for one to resolve a check is
+        // by blocking it, which can't resolve a double check
+        if checkers.is_empty() {
+            return true;
+        }
+
+        if checkers.is_many() {
+            return false;
+        }
+
+        let Some(checker) = Option::<Square>::from(checkers) else {
+            return false;
+        };
+
+        self.king_square(us).map_or(false, |king| {
+            accelerate::between(king, checker).contains(destination)
+        })
+    }
+
+    /// Returns [`true`] if the pawn move `mv` is a legitimate push, capture,
+    /// en passant capture, or promotion.
+    fn is_pseudo_legal_pawn_move(&self, mv: Move) -> bool {
+        let us          = self.turn;
+        let origin      = mv.origin();
+        let destination = mv.destination();
+        let up          = us.direction();
+
+        let (rank_2, rank_3) = match us {
+            Color::White => (Bitboard::RANK_2, Bitboard::RANK_3),
+            Color::Black => (Bitboard::RANK_7, Bitboard::RANK_6),
+        };
+
+        // pawns of the horde may also advance two squares from the first rank
+        let leaps = if self.ruleset == Ruleset::Horde { rank_2 | rank_3 } else { rank_3 };
+
+        let promotes = destination.from_perspective(us).rank() == Rank::_8;
+
+        match mv.move_type() {
+            MoveType::EnPassant => {
+                return self.state.en_passant == Some(destination)
+                    && (us | Token::Pawn).attacks(origin, self.bitboard()).contains(destination);
+            },
+
+            // promotions must be made upon reaching the final rank, and only
+            // then
+            MoveType::Promotion if !promotes => return false,
+            MoveType::Normal    if  promotes => return false,
+
+            // pawns may only promote to a king in antichess
+            MoveType::Promotion if mv.promotion() == Token::King
+                && self.ruleset != Ruleset::Antichess => return false,
+
+            _ => (),
+        }
+
+        let empty   = !self.bitboard();
+        let enemies = self.bitboard_for_color(!us);
+        let single  = (Bitboard::from(origin) + up) & empty;
+        let double  = ((single & leaps) + up) & empty;
+
+        ((us | Token::Pawn).attacks(origin, self.bitboard()) & enemies).contains(destination)
+            || single.contains(destination)
+            || double.contains(destination)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn legal_moves_start_position() {
+        let moves = Position::from_fen(Ruleset::Standard,
+            b"rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").legal_moves();
+
+        assert_eq!(20, moves.len());
+    }
+
+    #[test]
+    fn legal_moves_kiwipete() {
+        let moves = Position::from_fen(Ruleset::Standard,
+            b"r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1").legal_moves();
+
+        assert_eq!(48, moves.len());
+    }
+
+    #[test]
+    fn check_info() {
+        let position = Position::from_fen(Ruleset::Standard,
+            b"4k3/4r3/8/8/1b6/8/8/4K3 w - - 0 1");
+
+        assert_eq!(Square::B4 | Square::E7, position.checkers());
+
+        let position = Position::from_fen(Ruleset::Standard,
+            b"4k3/4r3/8/8/1b6/8/3NN3/4K3 w - - 0 1");
+
+        assert!(position.checkers().is_empty());
+        assert_eq!(Square::D2 | Square::E2, position.blockers_for_king(Color::White));
+        assert_eq!(Square::B4 | Square::E7, position.pinners(Color::Black));
+    }
+
+    #[test]
+    fn legal_moves_pinned() {
+        let moves = Position::from_fen(Ruleset::Standard,
+            b"4k3/4r3/8/8/8/8/4R3/4K3 w - - 0 1").legal_moves();
+
+        // the rook may only move along the e-file
+        assert!( moves.contains(&Move::new(Square::E2, Square::E7)));
+        assert!( moves.contains(&Move::new(Square::E2, Square::E4)));
+        assert!(!moves.contains(&Move::new(Square::E2, Square::D2)));
+    }
+
+    #[test]
+    fn legal_moves_double_check() {
+        let moves = Position::from_fen(Ruleset::Standard,
+            b"4k3/8/8/8/8/5n2/8/r3K2R w K - 0 1").legal_moves();
+
+        assert_eq!(2, moves.len());
+        assert!(moves.contains(&Move::new(Square::E1, Square::E2)));
+        assert!(moves.contains(&Move::new(Square::E1, Square::F2)));
+    }
+
+    #[test]
+    fn legal_moves_king_cannot_retreat_along_check() {
+        let moves = Position::from_fen(Ruleset::Standard,
+            b"4k3/8/8/8/8/8/8/r3K3 w - - 0 1").legal_moves();
+
+        assert!(!moves.contains(&Move::new(Square::E1, Square::F1)));
+        assert!(!moves.contains(&Move::new(Square::E1, Square::D1)));
+        assert!( moves.contains(&Move::new(Square::E1, Square::E2)));
+    }
+
+    #[test]
+    fn legal_moves_en_passant_discovered_check() {
+        let moves = Position::from_fen(Ruleset::Standard,
+            b"8/8/8/K2pP2r/8/8/8/7k w - d6 0 1").legal_moves();
+
+        assert!(!moves.contains(&Move::new_en_passant(Square::E5, Square::D6)));
+        assert!( moves.contains(&Move::new(Square::E5, Square::E6)));
+    }
+
+    #[test]
+    fn legal_moves_en_passant_evasion() {
+        let moves = Position::from_fen(Ruleset::Standard,
+            b"8/8/8/2k5/3Pp3/8/8/4K3 b - d3 0 1").legal_moves();
+
+        assert!(moves.contains(&Move::new_en_passant(Square::E4, Square::D3)));
+    }
+
+    #[test]
+    fn legal_moves_castling_through_check() {
+        let moves = Position::from_fen(Ruleset::Standard,
+            b"1r2kr2/8/8/8/8/8/8/R3K2R w KQ - 0 1").legal_moves();
+
+        // f1 is attacked, but b1 is only crossed by the rook
+        assert!(!moves.contains(&Move::new_castling(Square::E1, Square::H1)));
+        assert!( moves.contains(&Move::new_castling(Square::E1, Square::A1)));
+    }
+
+    #[test]
+    fn legal_moves_castling_into_check() {
+        let moves = Position::from_fen(Ruleset::Standard,
+            b"2r1k1r1/8/8/8/8/8/8/R3K2R w KQ - 0 1").legal_moves();
+
+        assert!(!moves.contains(&Move::new_castling(Square::E1, Square::H1)));
+        assert!(!moves.contains(&Move::new_castling(Square::E1, Square::A1)));
+    }
+
+    #[test]
+    fn legal_moves_castling_chess960_rook_pinned() {
+        let moves = Position::from_fen(Ruleset::Chess960,
+            b"4k3/8/8/8/8/8/8/qRK5 w B - 0 1").legal_moves();
+
+        // the castling rook is all that stands between the king and the queen
+        assert!(!moves.contains(&Move::new_castling(Square::C1, Square::B1)));
+    }
+
+    #[test]
+    fn is_pseudo_legal_matches_generation() {
+        for (ruleset, fen) in [
+            (Ruleset::Standard, "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"),
+            (Ruleset::Standard, "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1"),
+            (Ruleset::Standard, "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1"),
+            (Ruleset::Standard, "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1"),
+            (Ruleset::Standard, "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3"),
+            (Ruleset::Standard, "8/8/8/2k5/3Pp3/8/8/4K3 b - d3 0 1"),
+            (Ruleset::Standard, "4k3/8/8/8/8/5n2/8/r3K2R w K - 0 1"),
+            (Ruleset::Standard, "4r1k1/8/8/8/8/3n4/8/4KB2 w - - 0 1"),
+            (Ruleset::Standard, "4k3/8/8/8/8/8/4q3/R3K2R w KQ - 0 1"),
+            (Ruleset::Chess960, "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9"),
+            (Ruleset::Chess960, "4k3/8/8/8/8/8/8/qRK5 w B - 0 1"),
+            (Ruleset::Chess960, "1r2k3/8/8/8/8/8/8/RK5R w HA - 0 1"),
+            (Ruleset::Crazyhouse, "2k5/8/8/8/8/8/8/4K3[QRBNPqrbnp] w - - 0 1"),
+            (Ruleset::Crazyhouse, "4k3/8/8/8/8/8/8/r3K3[NPp] w - - 0 1"),
+            (Ruleset::Crazyhouse, "4k3/8/8/8/7b/8/8/r3K3[Q] w - - 0 1"),
+            (Ruleset::Atomic,     "rn2kb1r/1pp1p2p/p2q1pp1/3P4/2P3b1/4PN2/PP3PPP/R2QKB1R b KQkq - 0 1"),
+            (Ruleset::Atomic,     "4k3/3p4/8/8/B6q/8/8/4K3 w - - 0 1"),
+            (Ruleset::Antichess,  "rnbqkbnr/pppp1ppp/8/4p3/3P4/8/PPP1PPPP/RNBQKBNR w - - 0 2"),
+            (Ruleset::Antichess,  "1n2k3/P1P5/8/8/8/8/5K2/8 w - - 0 1"),
+            (Ruleset::Antichess,  "8/8/8/8/8/8/1p6/K1K5 w - - 0 1"),
+        ] {
+            let position = Position::from_fen(ruleset, fen.as_bytes());
+            let legal    = position.legal_moves();
+            let pseudo   = position.generate(if position.checkers().is_any() {
+                GenType::Evasions
+            } else {
+                GenType::NonEvasions
+            });
+
+            for mv in (0..=u16::MAX).filter_map(Move::from_u16) {
+                assert_eq!(pseudo.contains(&mv), position.is_pseudo_legal(mv), "{mv:?} in {fen}");
+                assert_eq!(legal .contains(&mv), position.is_legal(mv),        "{mv:?} in {fen}");
+            }
+        }
+    }
+
+    #[test]
+    fn is_legal_double_check() {
+        let position = Position::from_fen(Ruleset::Standard,
+            b"4r1k1/8/8/8/8/3n4/8/4KB2 w - - 0 1");
+
+        // capturing the knight still leaves the king in check from the rook
+        assert!(!position.is_legal(Move::new(Square::F1, Square::D3)));
+        assert!( position.is_legal(Move::new(Square::E1, Square::D2)));
+
+        let position = Position::from_fen(Ruleset::Crazyhouse,
+            b"4k3/8/8/8/7b/8/8/r3K3[Q] w - - 0 1");
+
+        // and blocking the rook still leaves it in check from the bishop
+        assert!(!position.is_legal(Move::new_drop(Token::Queen, Square::D1)));
+        assert!(!position.is_legal(Move::new_drop(Token::Queen, Square::F2)));
+    }
+
+    #[test]
+    fn legal_moves_antichess() {
+        let moves = Position::from_fen(Ruleset::Antichess,
+            b"rnbqkbnr/pppp1ppp/8/4p3/3P4/8/PPP1PPPP/RNBQKBNR w - - 0 2").legal_moves();
+
+        // capturing is compulsory
+        assert_eq!(vec![Move::new(Square::D4, Square::E5)], moves.to_vec());
+
+        // but with nothing to capture, any move may be made, including
+        // leaving the king attacked
+        let moves = Position::from_fen(Ruleset::Antichess,
+            b"4k3/8/8/8/8/8/8/r6K w - - 0 1").legal_moves();
+
+        assert_eq!(3, moves.len());
+        assert!(moves.contains(&Move::new(Square::H1, Square::G1)));
+
+        let moves = Position::from_fen(Ruleset::Antichess,
+            b"4k3/8/8/8/8/8/8/r3K3 b - - 0 1").legal_moves();
+
+        assert_eq!(vec![Move::new(Square::A1, Square::E1)], moves.to_vec());
+    }
+
+    #[test]
+    fn is_legal_king_promotion() {
+        let fen = b"8/P7/8/8/8/8/8/k6K w - - 0 1";
+        let mv  = Move::new_promote_king(Square::A7, Square::A8);
+
+        assert!(!Position::from_fen(Ruleset::Standard,  fen).is_legal(mv));
+        assert!( Position::from_fen(Ruleset::Antichess, fen).is_legal(mv));
+    }
+
+    #[test]
+    fn legal_moves_horde() {
+        let moves = Position::from_fen(Ruleset::Horde,
+            b"4k3/8/8/8/8/8/8/P7 w - - 0 1").legal_moves();
+
+        // a pawn of the horde may advance two squares from the first rank
+        assert_eq!(vec![
+            Move::new(Square::A1, Square::A2),
+            Move::new(Square::A1, Square::A3),
+        ], moves.to_vec());
+
+        let position = Position::from_fen(Ruleset::Horde,
+            b"4k3/8/8/8/8/P7/8/P7 w - - 0 1");
+
+        assert!(!position.is_legal(Move::new(Square::A1, Square::A3)));
+        assert!( position.is_legal(Move::new(Square::A1, Square::A2)));
+    }
+
+    #[test]
+    fn legal_moves_racing_kings() {
+        let position = Position::from_fen(Ruleset::RacingKings, b"8/8/8/8/8/k7/8/5R1K w - - 0 1");
+        let moves    = position.legal_moves();
+
+        // giving check is illegal
+        assert!(!moves.contains(&Move::new(Square::F1, Square::A1)));
+        assert!(!moves.contains(&Move::new(Square::F1, Square::F3)));
+        assert!( moves.contains(&Move::new(Square::F1, Square::B1)));
+
+        assert!(!position.is_legal(Move::new(Square::F1, Square::A1)));
+        assert!( position.is_legal(Move::new(Square::F1, Square::B1)));
+    }
+
+    #[test]
+    fn is_legal_foreign_moves() {
+        let position = Position::from_fen(Ruleset::Standard,
+            b"rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+
+        // moves of the wrong color, from empty squares, and of the wrong type
+        assert!(!position.is_legal(Move::new(Square::E7, Square::E5)));
+        assert!(!position.is_legal(Move::new(Square::E4, Square::E5)));
+        assert!(!position.is_legal(Move::new_en_passant(Square::E2, Square::D3)));
+        assert!(!position.is_legal(Move::new_promote_queen(Square::E2, Square::E3)));
+        assert!(!position.is_legal(Move::new_castling(Square::E1, Square::H1)));
+
+        // but the position is left untouched by asking
+        assert!(position.is_legal(Move::new(Square::E2, Square::E4)));
+        assert_eq!(20, position.legal_moves().len());
+    }
+}
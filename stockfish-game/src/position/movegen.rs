@@ -0,0 +1,722 @@
+use crate::prelude::*;
+use stockfish_core::prelude::*;
+use stockfish_core::accelerate;
+
+use core::ops::Deref;
+
+/// The kinds of moves that may be requested from [`Position::generate`].
+///
+/// These mirror the stages of move generation in Stockfish, which allow a
+/// search to inspect the most promising moves (captures) before bothering to
+/// generate the rest.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[must_use]
+pub enum GenType {
+    /// All pseudo-legal captures and queen promotions, plus underpromotions
+    /// that capture. In antichess, where captures are compulsory, this is
+    /// strictly the moves that capture.
+    Captures,
+
+    /// All pseudo-legal non-captures and underpromotions that don't capture,
+    /// along with queen promotions that don't capture in antichess.
+    Quiets,
+
+    /// All pseudo-legal non-captures that give check, other than castling and
+    /// promotions.
+    QuietChecks,
+
+    /// All pseudo-legal moves that might get the king out of check. Must only
+    /// be used when the side to move is in check.
+    Evasions,
+
+    /// All pseudo-legal captures and non-captures. Must only be used when the
+    /// side to move is not in check.
+    NonEvasions,
+}
+
+/// A fixed-capacity list of [`Move`]s, filled in by move generation.
+#[derive(Clone, Debug)]
+#[must_use]
+pub struct MoveList {
+    moves: [Move; MoveList::CAPACITY],
+    len:   usize,
+}
+
+impl MoveList {
+    /// The maximum number of moves that can be held by a [`MoveList`].
+    ///
+    /// The most moves known to be available in a legal chess position is 218,
+    /// but in crazyhouse every piece in hand may be dropped onto almost any
+    /// empty square, so this leaves room for several hundred drops on top of
+    /// the moves made on the board.
+    pub const CAPACITY: usize = 512;
+
+    // Move has no "empty" value, so unused slots are filled with an arbitrary
+    // move that is never exposed.
+    const UNUSED: Move = Move::new(Square::A1, Square::H8);
+
+    /// Creates an empty [`MoveList`].
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            moves: [Self::UNUSED; Self::CAPACITY],
+            len:   0,
+        }
+    }
+
+    /// Appends a move to the end of the list.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the list is already holding [`MoveList::CAPACITY`] moves.
+    #[inline]
+    pub fn push(&mut self, mv: Move) {
+        self.moves[self.len] = mv;
+        self.len            += 1;
+    }
+
+    /// Removes every move for which `predicate` returns [`false`]. The order of
+    /// the remaining moves is not preserved.
+    #[inline]
+    pub fn retain<F: FnMut(Move) -> bool>(&mut self, mut predicate: F) {
+        let mut i = 0;
+
+        while i < self.len {
+            if predicate(self.moves[i]) {
+                i += 1;
+            } else {
+                self.len     -= 1;
+                self.moves[i] = self.moves[self.len];
+            }
+        }
+    }
+
+    /// Sorts the moves by the key extracted from each with `f`. Moves with
+    /// equal keys may be reordered.
+    #[inline]
+    pub fn sort_unstable_by_key<K: Ord, F: FnMut(&Move) -> K>(&mut self, f: F) {
+        self.moves[..self.len].sort_unstable_by_key(f);
+    }
+}
+
+impl Default for MoveList {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Deref for MoveList {
+    type Target = [Move];
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.moves[..self.len]
+    }
+}
+
+impl<'a> IntoIterator for &'a MoveList {
+    type Item     = &'a Move;
+    type IntoIter = core::slice::Iter<'a, Move>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+#[allow(clippy::multiple_inherent_impl)]
+impl Position {
+    /// Generates every pseudo-legal move of the requested [`GenType`] for the
+    /// side to move.
+    ///
+    /// Pseudo-legal moves obey the movement rules of each piece, but may leave
+    /// the moving side's king in check.
+    pub fn generate(&self, kind: GenType) -> MoveList {
+        let mut moves = MoveList::new();
+
+        self.generate_into(kind, &mut moves);
+
+        moves
+    }
+
+    /// Like [`Position::generate`], but appends the generated moves to an
+    /// existing [`MoveList`].
+    pub fn generate_into(&self, kind: GenType, moves: &mut MoveList) {
+        let us        = self.turn;
+        let checkers  = self.state.checkers;
+        let checks    = self.check_info(kind);
+        let antichess = self.ruleset == Ruleset::Antichess;
+
+        debug_assert_eq!(kind == GenType::Evasions, checkers.is_any(),
+            "evasions must be generated if and only if in check");
+
+        // in atomic chess a check can also be answered by blowing up the
+        // checker or the enemy king from afar, so every move is a candidate
+        let kind = match kind {
+            GenType::Evasions if self.ruleset == Ruleset::Atomic => GenType::NonEvasions,
+            kind                                                 => kind,
+        };
+
+        // there's no such thing as check in antichess, and giving one is
+        // illegal in racing kings
+        if kind == GenType::QuietChecks && (antichess || self.ruleset == Ruleset::RacingKings) {
+            return;
+        }
+
+        // in double check only the king can move, so we skip generating moves
+        // for every other piece
+        if kind != GenType::Evasions || !checkers.is_many() {
+            let target = match kind {
+                GenType::Captures    => self.bitboard_for_color(!us),
+                GenType::NonEvasions => !self.bitboard_for_color(us),
+                GenType::Evasions    => self.evasion_target(checkers),
+
+                GenType::Quiets | GenType::QuietChecks => !self.bitboard(),
+            };
+
+            self.generate_pawn_moves(kind, target, checks, moves);
+
+            for token in [Token::Knight, Token::Bishop, Token::Rook, Token::Queen] {
+                self.generate_token_moves(token, target, checks, moves);
+            }
+
+            if self.ruleset == Ruleset::Crazyhouse && kind != GenType::Captures {
+                self.generate_drops(target & !self.bitboard(), checks, moves);
+            }
+
+            // kings are ordinary pieces in antichess, which can't castle and
+            // may be captured, and a side may have any number of them
+            if antichess {
+                self.generate_token_moves(Token::King, target, checks, moves);
+                return;
+            }
+        }
+
+        self.generate_king_moves(kind, checks, moves);
+    }
+
+    /// When in check, every non-king move must either capture the checking
+    /// piece or interpose itself between the checker and the king.
+    fn evasion_target(&self, checkers: Bitboard) -> Bitboard {
+        let checker = Option::<Square>::from(checkers);
+
+        self.king_square(self.turn).zip(checker).map_or(
+            Bitboard::EMPTY,
+            |(king, checker)| accelerate::between(king, checker),
+        )
+    }
+
+    /// When generating quiet checks, returns the enemy king's square along
+    /// with our pieces that would give a discovered check by moving out of
+    /// the way.
+    fn check_info(&self, kind: GenType) -> Option<(Square, Bitboard)> {
+        if kind != GenType::QuietChecks {
+            return None;
+        }
+
+        let king = self.king_square(!self.turn)?;
+
+        Some((king, self.discovered_check_candidates()))
+    }
+
+    fn generate_pawn_moves(
+        &self,
+        kind:   GenType,
+        target: Bitboard,
+        checks: Option<(Square, Bitboard)>,
+        moves:  &mut MoveList,
+    ) {
+        let us        = self.turn;
+        let them      = !us;
+        let antichess = self.ruleset == Ruleset::Antichess;
+
+        let (rank_2, rank_3, rank_7) = match us {
+            Color::White => (Bitboard::RANK_2, Bitboard::RANK_3, Bitboard::RANK_7),
+            Color::Black => (Bitboard::RANK_7, Bitboard::RANK_6, Bitboard::RANK_2),
+        };
+
+        // pawns of the horde may also advance two squares from the first rank
+        let leaps = if self.ruleset == Ruleset::Horde { rank_2 | rank_3 } else { rank_3 };
+
+        let (up, up_right, up_left) = match us {
+            Color::White => (Direction::N, Direction::NE, Direction::NW),
+            Color::Black => (Direction::S, Direction::SW, Direction::SE),
+        };
+
+        let empty   = !self.bitboard();
+        let enemies = match kind {
+            GenType::Evasions => self.state.checkers,
+            _                 => self.bitboard_for_color(them),
+        };
+
+        let pawns          = self.bitboard_for_token(us | Token::Pawn);
+        let pawns_on_7     = pawns &  rank_7;
+        let pawns_not_on_7 = pawns & !rank_7;
+
+        // single and double pawn pushes, excluding promotions
+        if kind != GenType::Captures {
+            let mut single = (pawns_not_on_7 + up)    & empty;
+            let mut double = ((single & leaps) + up) & empty;
+
+            // only pushes that block the check are useful
+            if kind == GenType::Evasions {
+                single &= target;
+                double &= target;
+            }
+
+            // a quiet check can be made by pushing a pawn to a square that
+            // attacks the king directly, or by pushing a pawn out of the way
+            // of a discovered check (as long as it's not staying on the file)
+            if let Some((king, discoverers)) = checks {
+                let candidates = discoverers & !Bitboard::from(king.file());
+                let direct     = self.state.check_squares[Token::Pawn];
+
+                single &= direct | (candidates + up);
+                double &= direct | (candidates + up + up);
+            }
+
+            for destination in single {
+                moves.push(Move::new(destination.wrapping_sub(up), destination));
+            }
+
+            for destination in double {
+                moves.push(Move::new(destination.wrapping_sub(up).wrapping_sub(up), destination));
+            }
+        }
+
+        // promotions and underpromotions
+        if pawns_on_7.is_any() {
+            let captures_right = (pawns_on_7 + up_right) & enemies;
+            let captures_left  = (pawns_on_7 + up_left)  & enemies;
+            let mut pushes     = (pawns_on_7 + up)       & empty;
+
+            if kind == GenType::Evasions {
+                pushes &= target;
+            }
+
+            for destination in captures_right {
+                push_promotions(kind, destination.wrapping_sub(up_right), destination, true, antichess, moves);
+            }
+
+            for destination in captures_left {
+                push_promotions(kind, destination.wrapping_sub(up_left), destination, true, antichess, moves);
+            }
+
+            for destination in pushes {
+                push_promotions(kind, destination.wrapping_sub(up), destination, false, antichess, moves);
+            }
+        }
+
+        // standard and en passant captures
+        if matches!(kind, GenType::Captures | GenType::Evasions | GenType::NonEvasions) {
+            let captures_right = (pawns_not_on_7 + up_right) & enemies;
+            let captures_left  = (pawns_not_on_7 + up_left)  & enemies;
+
+            for destination in captures_right {
+                moves.push(Move::new(destination.wrapping_sub(up_right), destination));
+            }
+
+            for destination in captures_left {
+                moves.push(Move::new(destination.wrapping_sub(up_left), destination));
+            }
+
+            if let Some(en_passant) = self.state.en_passant {
+                // an en passant capture can't resolve a check that was
+                // discovered by the enemy pawn's double push
+                if kind == GenType::Evasions && target.contains(en_passant.wrapping_add(up)) {
+                    return;
+                }
+
+                let capturers = pawns_not_on_7
+                    & (them | Token::Pawn).attacks(en_passant, self.bitboard());
+
+                for origin in capturers {
+                    moves.push(Move::new_en_passant(origin, en_passant));
+                }
+            }
+        }
+    }
+
+    fn generate_token_moves(
+        &self,
+        token:  Token,
+        target: Bitboard,
+        checks: Option<(Square, Bitboard)>,
+        moves:  &mut MoveList,
+    ) {
+        let occupancy = self.bitboard();
+
+        for origin in self.bitboard_for_token(self.turn | token) {
+            let mut destinations = token.attacks(origin, occupancy) & target;
+
+            // to give a quiet check, a piece must either move out of the way
+            // of a discovered check or move to a square attacking the king;
+            // queens can't discover a check, since they'd already be giving
+            // check along that line themselves
+            if let Some((_, discoverers)) = checks {
+                if token == Token::Queen || discoverers.omits(origin) {
+                    destinations &= self.state.check_squares[token];
+                }
+            }
+
+            for destination in destinations {
+                moves.push(Move::new(origin, destination));
+            }
+        }
+    }
+
+    /// Generates drops of every piece in the side to move's pocket onto the
+    /// empty squares in `target`. Drops never capture, and can only block a
+    /// check rather than capture the checker.
+    fn generate_drops(
+        &self,
+        target: Bitboard,
+        checks: Option<(Square, Bitboard)>,
+        moves:  &mut MoveList,
+    ) {
+        for token in [Token::Pawn, Token::Knight, Token::Bishop, Token::Rook, Token::Queen] {
+            if self.pockets[self.turn | token] == 0 {
+                continue;
+            }
+
+            let mut destinations = target;
+
+            if token == Token::Pawn {
+                destinations &= !(Bitboard::RANK_1 | Bitboard::RANK_8);
+            }
+
+            // a dropped piece can only ever check the king directly
+            if checks.is_some() {
+                destinations &= self.state.check_squares[token];
+            }
+
+            for destination in destinations {
+                moves.push(Move::new_drop(token, destination));
+            }
+        }
+    }
+
+    fn generate_king_moves(
+        &self,
+        kind:   GenType,
+        checks: Option<(Square, Bitboard)>,
+        moves:  &mut MoveList,
+    ) {
+        let us = self.turn;
+
+        let Some(king) = self.king_square(us) else { return };
+
+        let mut destinations = Token::King.moves(king) & match kind {
+            GenType::Captures                        => self.bitboard_for_color(!us),
+            GenType::Quiets   | GenType::QuietChecks => !self.bitboard(),
+            GenType::Evasions | GenType::NonEvasions => !self.bitboard_for_color(us),
+        };
+
+        // in atomic chess the king can't capture, since it would be caught in
+        // the explosion
+        if self.ruleset == Ruleset::Atomic {
+            destinations &= !self.bitboard();
+        }
+
+        // the king can only give a quiet check by discovery, so it must be
+        // blocking a slider and must step off of the line to the enemy king
+        if let Some((enemy_king, discoverers)) = checks {
+            if discoverers.omits(king) {
+                return;
+            }
+
+            destinations &= !Token::Queen.moves(enemy_king);
+        }
+
+        for destination in destinations {
+            moves.push(Move::new(king, destination));
+        }
+
+        // castling out of check is never allowed, even in atomic chess where
+        // non-evasions are generated while in check
+        if matches!(kind, GenType::Quiets | GenType::NonEvasions) && self.state.checkers.is_empty() {
+            for side in CastlingSide::iter() {
+                let variety = CastlingVariety::new(us, side);
+
+                let Some(path) = self.castling_path(variety) else { continue };
+
+                if self.can_castle(path.rights()) && !self.is_castling_impeded(path) {
+                    moves.push(Move::new_castling(path.king_origin(), path.rook_origin()));
+                }
+            }
+        }
+    }
+}
+
+/// Pushes the promotions of a pawn moving from `origin` to `destination` that
+/// belong to the requested [`GenType`].
+///
+/// Queen promotions are considered alongside captures, since they're almost
+/// always worth looking at early. Underpromotions are only considered
+/// captures if they actually capture something. In antichess, pawns may also
+/// promote to a king, and since captures are compulsory, promotions of any
+/// kind are only considered captures if they capture.
+fn push_promotions(
+    kind:        GenType,
+    origin:      Square,
+    destination: Square,
+    capture:     bool,
+    antichess:   bool,
+    moves:       &mut MoveList,
+) {
+    let all   = matches!(kind, GenType::Evasions | GenType::NonEvasions);
+    let early = capture || !antichess;
+
+    if (kind == GenType::Captures && early) || (kind == GenType::Quiets && !early) || all {
+        moves.push(Move::new_promote_queen(origin, destination));
+    }
+
+    if (kind == GenType::Captures && capture) || (kind == GenType::Quiets && !capture) || all {
+        moves.push(Move::new_promote_rook  (origin, destination));
+        moves.push(Move::new_promote_bishop(origin, destination));
+        moves.push(Move::new_promote_knight(origin, destination));
+
+        if antichess {
+            moves.push(Move::new_promote_king(origin, destination));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn move_list_push_and_retain() {
+        let mut moves = MoveList::new();
+
+        assert!(moves.is_empty());
+
+        moves.push(Move::new(Square::E2, Square::E4));
+        moves.push(Move::new(Square::D2, Square::D4));
+        moves.push(Move::new(Square::G1, Square::F3));
+
+        assert_eq!(3, moves.len());
+
+        moves.retain(|mv| mv.origin() != Square::E2);
+
+        assert_eq!(2, moves.len());
+        assert!(!moves.contains(&Move::new(Square::E2, Square::E4)));
+        assert!( moves.contains(&Move::new(Square::D2, Square::D4)));
+        assert!( moves.contains(&Move::new(Square::G1, Square::F3)));
+    }
+
+    #[test]
+    fn move_list_sort_unstable_by_key() {
+        let mut moves = MoveList::new();
+
+        moves.push(Move::new(Square::E2, Square::E4));
+        moves.push(Move::new(Square::D2, Square::D4));
+        moves.push(Move::new(Square::G1, Square::F3));
+
+        moves.sort_unstable_by_key(|mv| mv.origin() as u8);
+
+        assert_eq!([
+            Move::new(Square::G1, Square::F3),
+            Move::new(Square::D2, Square::D4),
+            Move::new(Square::E2, Square::E4),
+        ], *moves);
+    }
+
+    #[test]
+    fn generate_start_position() {
+        let position = Position::from_fen(Ruleset::Standard,
+            b"rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+
+        assert_eq!(20, position.generate(GenType::NonEvasions).len());
+        assert_eq!(20, position.generate(GenType::Quiets)     .len());
+        assert_eq!(0,  position.generate(GenType::Captures)   .len());
+        assert_eq!(0,  position.generate(GenType::QuietChecks).len());
+    }
+
+    #[test]
+    fn generate_kiwipete() {
+        let position = Position::from_fen(Ruleset::Standard,
+            b"r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1");
+
+        let captures = position.generate(GenType::Captures);
+        let quiets   = position.generate(GenType::Quiets);
+        let all      = position.generate(GenType::NonEvasions);
+
+        assert_eq!(48, all.len());
+        assert_eq!(8,  captures.len());
+        assert_eq!(40, quiets.len());
+
+        assert!(quiets.contains(&Move::new_castling(Square::E1, Square::H1)));
+        assert!(quiets.contains(&Move::new_castling(Square::E1, Square::A1)));
+    }
+
+    #[test]
+    fn generate_castling_chess960() {
+        let position = Position::from_fen(
+            Ruleset::Chess960,
+            b"1r2k1r1/8/8/8/8/8/8/1R2K1R1 w GBgb - 0 1",
+        );
+
+        let quiets = position.generate(GenType::Quiets);
+
+        assert!(quiets.contains(&Move::new_castling(Square::E1, Square::G1)));
+        assert!(quiets.contains(&Move::new_castling(Square::E1, Square::B1)));
+    }
+
+    #[test]
+    fn generate_castling_impeded() {
+        let position = Position::from_fen(Ruleset::Standard,
+            b"r3k2r/8/8/8/8/8/8/RN2K1NR w KQkq - 0 1");
+
+        let quiets = position.generate(GenType::Quiets);
+
+        assert!(!quiets.contains(&Move::new_castling(Square::E1, Square::H1)));
+        assert!(!quiets.contains(&Move::new_castling(Square::E1, Square::A1)));
+    }
+
+    #[test]
+    fn generate_promotions() {
+        let position = Position::from_fen(Ruleset::Standard, b"1n5k/P7/8/8/8/8/8/7K w - - 0 1");
+
+        let captures = position.generate(GenType::Captures);
+        let quiets   = position.generate(GenType::Quiets);
+
+        // queen promotions are generated alongside captures, as are capturing
+        // underpromotions
+        assert!(captures.contains(&Move::new_promote_queen (Square::A7, Square::A8)));
+        assert!(captures.contains(&Move::new_promote_queen (Square::A7, Square::B8)));
+        assert!(captures.contains(&Move::new_promote_knight(Square::A7, Square::B8)));
+
+        // non-capturing underpromotions are considered quiet
+        assert!( quiets.contains(&Move::new_promote_rook (Square::A7, Square::A8)));
+        assert!(!quiets.contains(&Move::new_promote_queen(Square::A7, Square::A8)));
+        assert!(!quiets.contains(&Move::new_promote_rook (Square::A7, Square::B8)));
+    }
+
+    #[test]
+    fn generate_promotions_antichess() {
+        let position = Position::from_fen(Ruleset::Antichess, b"1n6/P7/8/8/8/8/8/7K w - - 0 1");
+
+        let captures = position.generate(GenType::Captures);
+        let quiets   = position.generate(GenType::Quiets);
+
+        // pawns may promote to a king, and only promotions that capture are
+        // generated alongside captures
+        assert_eq!(5, captures.len());
+        assert!(captures.iter().all(|mv| mv.destination() == Square::B8));
+        assert!(captures.contains(&Move::new_promote_king(Square::A7, Square::B8)));
+
+        assert!(quiets.contains(&Move::new_promote_queen(Square::A7, Square::A8)));
+        assert!(quiets.contains(&Move::new_promote_king (Square::A7, Square::A8)));
+    }
+
+    #[test]
+    fn generate_antichess_kings() {
+        let position = Position::from_fen(Ruleset::Antichess, b"8/8/8/8/8/8/1p6/K1K5 w - - 0 1");
+
+        // every king may move, and may capture
+        assert_eq!(2, position.generate(GenType::Captures).len());
+        assert_eq!(8, position.generate(GenType::NonEvasions).len());
+        assert!(position.generate(GenType::QuietChecks).is_empty());
+    }
+
+    #[test]
+    fn generate_en_passant() {
+        let position = Position::from_fen(Ruleset::Standard,
+            b"rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3");
+
+        let captures = position.generate(GenType::Captures);
+
+        assert_eq!(1, captures.len());
+        assert!(captures.contains(&Move::new_en_passant(Square::E5, Square::F6)));
+    }
+
+    #[test]
+    fn generate_evasions() {
+        let position = Position::from_fen(Ruleset::Standard,
+            b"4k3/8/8/8/1b6/8/2P5/R3K2R w KQ - 0 1");
+
+        let evasions = position.generate(GenType::Evasions);
+
+        // the king may step away and the pawn may block, but the pawn's double
+        // push doesn't resolve the check and castling is never generated out of
+        // check
+        assert!( evasions.contains(&Move::new(Square::E1, Square::F2)));
+        assert!( evasions.contains(&Move::new(Square::C2, Square::C3)));
+        assert!(!evasions.contains(&Move::new(Square::C2, Square::C4)));
+        assert!(!evasions.contains(&Move::new(Square::A1, Square::A2)));
+        assert!(!evasions.contains(&Move::new_castling(Square::E1, Square::H1)));
+    }
+
+    #[test]
+    fn generate_evasions_double_check() {
+        let position = Position::from_fen(Ruleset::Standard, b"4k3/8/8/8/8/5n2/8/r3K2R w K - 0 1");
+        let evasions = position.generate(GenType::Evasions);
+
+        // only king moves are generated in double check
+        assert!(evasions.iter().all(|mv| mv.origin() == Square::E1));
+    }
+
+    #[test]
+    fn generate_quiet_checks() {
+        let position = Position::from_fen(Ruleset::Standard, b"4k3/8/8/8/8/8/4B3/3QK1N1 w - - 0 1");
+        let checks   = position.generate(GenType::QuietChecks);
+
+        // queen checks along the d-file and diagonal
+        assert!(checks.contains(&Move::new(Square::D1, Square::D7)));
+        assert!(checks.contains(&Move::new(Square::D1, Square::A4)));
+
+        // bishop checks from b5 and h5
+        assert!(checks.contains(&Move::new(Square::E2, Square::B5)));
+        assert!(checks.contains(&Move::new(Square::E2, Square::H5)));
+
+        // the knight can't reach a checking square
+        assert!(checks.iter().all(|mv| mv.origin() != Square::G1));
+
+        assert!(checks.iter().all(|mv| mv.origin() != Square::E1));
+    }
+
+    #[test]
+    fn generate_drops() {
+        let position = Position::from_fen(Ruleset::Crazyhouse,
+            b"4k3/8/8/8/8/8/8/4K3[Pn] w - - 0 1");
+
+        let quiets = position.generate(GenType::Quiets);
+
+        // pawns are never dropped onto the first or eighth rank, and black's
+        // knight can't be dropped on white's turn
+        assert_eq!(5 + 48, quiets.len());
+        assert!(quiets.contains(&Move::new_drop(Token::Pawn, Square::A2)));
+        assert!(quiets.iter()
+            .filter(|mv| mv.move_type() == MoveType::Drop)
+            .all(|mv| mv.dropped() == Token::Pawn && mv.destination().rank() != Rank::_1));
+
+        assert!(position.generate(GenType::Captures).is_empty());
+    }
+
+    #[test]
+    fn generate_drops_evasions() {
+        let position = Position::from_fen(Ruleset::Crazyhouse,
+            b"4k3/8/8/8/8/8/8/r3K3[N] w - - 0 1");
+
+        let evasions = position.generate(GenType::Evasions);
+
+        // a dropped piece can only block the check
+        let drops = evasions.iter().filter(|mv| mv.move_type() == MoveType::Drop);
+
+        assert_eq!(vec![Square::B1, Square::C1, Square::D1],
+            drops.map(|mv| mv.destination()).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn generate_quiet_checks_discovered() {
+        let position = Position::from_fen(Ruleset::Standard, b"4k3/8/8/8/8/8/4N3/4RK2 w - - 0 1");
+        let checks   = position.generate(GenType::QuietChecks);
+
+        // every knight move from e2 discovers a check from the rook
+        assert_eq!(6, checks.len());
+        assert!(checks.iter().all(|mv| mv.origin() == Square::E2));
+    }
+}
@@ -0,0 +1,518 @@
+use crate::prelude::*;
+use crate::position::draw::FIFTY_MOVES;
+use stockfish_core::prelude::*;
+
+use core::fmt::{Display, Formatter, Result as FmtResult};
+
+/// The number of halfmoves without a capture or pawn advance after which the
+/// game is automatically drawn.
+const SEVENTY_FIVE_MOVES: u8 = 150;
+
+/// The result of a game, as determined by [`Position::outcome`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum GameOutcome {
+    /// The game hasn't ended.
+    Ongoing,
+
+    /// The game was won by `winner`.
+    Decisive { winner: Color, termination: Termination },
+
+    /// The game was drawn.
+    Draw { termination: Termination },
+}
+
+/// The reason a game ended.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Termination {
+    /// The side to move is in check and has no legal moves.
+    Checkmate,
+
+    /// The side to move isn't in check but has no legal moves. This is a
+    /// draw, except in antichess where the stalemated side wins.
+    Stalemate,
+
+    /// A king was blown up by a capture next to it in atomic chess.
+    Explosion,
+
+    /// A side lost every one of its pieces, winning the game in antichess but
+    /// losing it as the horde in horde.
+    LostAllPieces,
+
+    /// A side gave check for the third time in three-check.
+    ThreeChecks,
+
+    /// A king reached one of the four central squares in king of the hill.
+    KingOfTheHill,
+
+    /// A king reached the eighth rank in racing kings. Both kings reaching it
+    /// draws the game.
+    RacingKings,
+
+    /// Neither side has enough material left to deliver checkmate.
+    InsufficientMaterial,
+
+    /// Seventy-five moves were made by each side without a capture or pawn
+    /// advance. The game is drawn automatically.
+    SeventyFiveMoves,
+
+    /// The same position occurred for the fifth time. The game is drawn
+    /// automatically.
+    FivefoldRepetition,
+
+    /// Fifty moves were made by each side without a capture or pawn advance.
+    /// The draw must be claimed by one of the players.
+    FiftyMoves,
+
+    /// The same position occurred for the third time. The draw must be
+    /// claimed by one of the players.
+    ThreefoldRepetition,
+}
+
+#[allow(clippy::multiple_inherent_impl)]
+impl Position {
+    /// Classifies the current position as a win, a draw, or an ongoing game.
+    ///
+    /// Checkmate takes precedence over every other termination, followed by
+    /// those which end the game automatically, and finally those which would
+    /// have to be claimed by one of the players. Repetitions are only counted
+    /// since the last irreversible move.
+    ///
+    /// Wins particular to the variant being played, as found by
+    /// [`Position::variant_outcome`], take precedence over everything else.
+    /// In antichess, the side which is stalemated wins instead of drawing.
+    #[must_use]
+    pub fn outcome(&self) -> GameOutcome {
+        if let Some(outcome) = self.variant_outcome() {
+            return outcome;
+        }
+
+        let no_moves = self.legal_moves().is_empty();
+
+        // in antichess the goal is to give away everything, so the side left
+        // without any moves wins
+        if no_moves && self.ruleset == Ruleset::Antichess {
+            return GameOutcome::Decisive {
+                winner:      self.turn(),
+                termination: Termination::Stalemate,
+            };
+        }
+
+        if no_moves && self.checkers().is_any() {
+            return GameOutcome::Decisive {
+                winner:      !self.turn(),
+                termination: Termination::Checkmate,
+            };
+        }
+
+        let termination = if no_moves {
+            Termination::Stalemate
+        } else if self.has_insufficient_material() {
+            Termination::InsufficientMaterial
+        } else if self.halfmoves() >= SEVENTY_FIVE_MOVES {
+            Termination::SeventyFiveMoves
+        } else if self.has_repeated(4) {
+            Termination::FivefoldRepetition
+        } else if self.halfmoves() >= FIFTY_MOVES {
+            Termination::FiftyMoves
+        } else if self.has_repeated(2) {
+            Termination::ThreefoldRepetition
+        } else {
+            return GameOutcome::Ongoing;
+        };
+
+        GameOutcome::Draw { termination }
+    }
+
+    /// Returns the outcome of the game if it has been decided by one of the
+    /// rules particular to the variant being played: a king being blown up
+    /// in atomic chess, a side losing all of its pieces in antichess or
+    /// horde, the third check in three-check, a king reaching the center in
+    /// king of the hill, or a king reaching the eighth rank in racing kings.
+    ///
+    /// Unlike [`Position::outcome`], this doesn't need to generate any moves,
+    /// so it's cheap enough to be checked at every node of a search.
+    #[must_use]
+    pub fn variant_outcome(&self) -> Option<GameOutcome> {
+        let decisive = |winner, termination| GameOutcome::Decisive { winner, termination };
+
+        match self.ruleset {
+            Ruleset::Atomic => Color::iter()
+                .find(|&color| self.king_square(color).is_none())
+                .map(|loser| decisive(!loser, Termination::Explosion)),
+
+            Ruleset::Antichess => Color::iter()
+                .find(|&color| self.bitboard_for_color(color).is_empty())
+                .map(|winner| decisive(winner, Termination::LostAllPieces)),
+
+            Ruleset::ThreeCheck => Color::iter()
+                .find(|&color| usize::from(self.state.checks[color]) >= Ruleset::MAX_CHECKS)
+                .map(|winner| decisive(winner, Termination::ThreeChecks)),
+
+            Ruleset::KingOfTheHill => Color::iter()
+                .find(|&color| self.bitboard_for_token(color | Token::King).overlaps(Bitboard::CENTER))
+                .map(|winner| decisive(winner, Termination::KingOfTheHill)),
+
+            Ruleset::Horde => self.bitboard_for_color(Color::White).is_empty()
+                .then(|| decisive(Color::Black, Termination::LostAllPieces)),
+
+            Ruleset::RacingKings => self.racing_kings_outcome(),
+
+            Ruleset::Standard | Ruleset::Chess960 | Ruleset::Crazyhouse => None,
+        }
+    }
+
+    /// Returns the outcome of a game of racing kings once a king has reached
+    /// the eighth rank.
+    ///
+    /// White moves first, so when the white king gets there black is given
+    /// one more move to draw by reaching it as well. The game is only over
+    /// straight away if black has no way to do so.
+    fn racing_kings_outcome(&self) -> Option<GameOutcome> {
+        let arrived = |color| self.bitboard_for_token(color | Token::King).overlaps(Bitboard::RANK_8);
+        let white   = GameOutcome::Decisive { winner: Color::White, termination: Termination::RacingKings };
+
+        match (arrived(Color::White), arrived(Color::Black)) {
+            (true,  true)  => Some(GameOutcome::Draw { termination: Termination::RacingKings }),
+            (false, true)  => Some(GameOutcome::Decisive { winner: Color::Black, termination: Termination::RacingKings }),
+            (false, false) => None,
+
+            (true, false) if self.turn.is_white() => Some(white),
+            (true, false) => {
+                let Some(king) = self.king_square(Color::Black) else { return Some(white) };
+
+                let catches_up = (Token::King.moves(king) & Bitboard::RANK_8).iter().any(|destination| {
+                    let mv = Move::new(king, destination);
+
+                    self.is_pseudo_legal(mv) && self.is_legal_unchecked(mv)
+                });
+
+                (!catches_up).then_some(white)
+            },
+        }
+    }
+}
+
+impl GameOutcome {
+    /// Returns the winning side, if any.
+    #[inline]
+    #[must_use]
+    pub const fn winner(self) -> Option<Color> {
+        match self {
+            Self::Decisive { winner, .. } => Some(winner),
+            _                             => None,
+        }
+    }
+
+    /// Returns the reason the game ended, if it has.
+    #[inline]
+    #[must_use]
+    pub const fn termination(self) -> Option<Termination> {
+        match self {
+            Self::Decisive { termination, .. } |
+            Self::Draw     { termination }     => Some(termination),
+            Self::Ongoing                      => None,
+        }
+    }
+
+    /// Returns [`true`] if the game hasn't ended.
+    #[inline]
+    #[must_use]
+    pub const fn is_ongoing(self) -> bool {
+        matches!(self, Self::Ongoing)
+    }
+}
+
+impl Termination {
+    /// Returns [`true`] if the game ends as soon as this termination occurs,
+    /// rather than requiring one of the players to claim it.
+    #[inline]
+    #[must_use]
+    pub const fn is_automatic(self) -> bool {
+        !matches!(self, Self::FiftyMoves | Self::ThreefoldRepetition)
+    }
+}
+
+impl Display for GameOutcome {
+    /// Writes the outcome as a PGN result token.
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.write_str(match *self {
+            Self::Ongoing                 => "*",
+            Self::Draw     { .. }         => "1/2-1/2",
+            Self::Decisive { winner, .. } => if winner.is_white() { "1-0" } else { "0-1" },
+        })
+    }
+}
+
+impl Display for Termination {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.write_str(match *self {
+            Self::Checkmate            => "checkmate",
+            Self::Stalemate            => "stalemate",
+            Self::Explosion            => "explosion",
+            Self::LostAllPieces        => "all pieces lost",
+            Self::ThreeChecks          => "three checks",
+            Self::KingOfTheHill        => "king of the hill",
+            Self::RacingKings          => "racing kings",
+            Self::InsufficientMaterial => "insufficient material",
+            Self::SeventyFiveMoves     => "seventy-five-move rule",
+            Self::FivefoldRepetition   => "fivefold repetition",
+            Self::FiftyMoves           => "fifty-move rule",
+            Self::ThreefoldRepetition  => "threefold repetition",
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::position::draw::SHUFFLE;
+
+    #[test]
+    fn outcome_ongoing() {
+        let outcome = Position::from_fen(Ruleset::Standard,
+            b"rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").outcome();
+
+        assert_eq!(GameOutcome::Ongoing, outcome);
+        assert_eq!(None, outcome.winner());
+        assert_eq!(None, outcome.termination());
+        assert_eq!("*",  outcome.to_string());
+    }
+
+    #[test]
+    fn outcome_checkmate() {
+        let outcome = Position::from_fen(Ruleset::Standard,
+            b"rnb1kbnr/pppp1ppp/8/4p3/6Pq/5P2/PPPPP2P/RNBQKBNR w KQkq - 1 3").outcome();
+
+        assert_eq!(Some(Color::Black),           outcome.winner());
+        assert_eq!(Some(Termination::Checkmate), outcome.termination());
+        assert_eq!("0-1",                        outcome.to_string());
+
+        let outcome = Position::from_fen(Ruleset::Standard,
+            b"7k/6Q1/6K1/8/8/8/8/8 b - - 0 1").outcome();
+
+        assert_eq!(Some(Color::White), outcome.winner());
+        assert_eq!("1-0",              outcome.to_string());
+    }
+
+    #[test]
+    fn outcome_checkmate_overrides_seventy_five_moves() {
+        let position = Position::from_fen(Ruleset::Standard,
+            b"7k/6Q1/6K1/8/8/8/8/8 b - - 150 100");
+
+        assert_eq!(Some(Color::White), position.outcome().winner());
+    }
+
+    #[test]
+    fn outcome_stalemate() {
+        let outcome = Position::from_fen(Ruleset::Standard,
+            b"7k/5Q2/6K1/8/8/8/8/8 b - - 0 1").outcome();
+
+        assert_eq!(GameOutcome::Draw { termination: Termination::Stalemate }, outcome);
+        assert_eq!("1/2-1/2", outcome.to_string());
+    }
+
+    #[test]
+    fn outcome_insufficient_material() {
+        let position = Position::from_fen(Ruleset::Standard,
+            b"4k3/8/8/8/8/8/8/4KN2 w - - 0 1");
+
+        assert_eq!(GameOutcome::Draw { termination: Termination::InsufficientMaterial },
+            position.outcome());
+    }
+
+    #[test]
+    fn outcome_move_rules() {
+        for (termination, fen) in [
+            (Termination::FiftyMoves,       "4k3/8/8/8/8/8/8/4K2R w - - 100 80"),
+            (Termination::SeventyFiveMoves, "4k3/8/8/8/8/8/8/4K2R w - - 150 80"),
+        ] {
+            let position = Position::from_fen(Ruleset::Standard, fen.as_bytes());
+
+            assert_eq!(GameOutcome::Draw { termination }, position.outcome(), "{fen}");
+        }
+
+        let position = Position::from_fen(Ruleset::Standard,
+            b"4k3/8/8/8/8/8/8/4K2R w - - 99 80");
+
+        assert_eq!(GameOutcome::Ongoing, position.outcome());
+
+        // a halfmove clock too long to be stored still ends the game
+        let position = Position::try_from_fen(Ruleset::Standard,
+            b"4k3/8/8/8/8/8/8/4K2R w - - 256 200").unwrap();
+
+        assert_eq!(Some(Termination::SeventyFiveMoves), position.outcome().termination());
+    }
+
+    #[test]
+    fn outcome_repetition() {
+        let mut position = Position::from_fen(Ruleset::Standard,
+            b"rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+
+        let expected = [
+            GameOutcome::Ongoing,
+            GameOutcome::Draw { termination: Termination::ThreefoldRepetition },
+            GameOutcome::Draw { termination: Termination::ThreefoldRepetition },
+            GameOutcome::Draw { termination: Termination::FivefoldRepetition },
+        ];
+
+        for outcome in expected {
+            for mv in SHUFFLE {
+                position.do_move(mv);
+            }
+
+            assert_eq!(outcome, position.outcome());
+        }
+    }
+
+    #[test]
+    fn outcome_explosion() {
+        let mut position = Position::from_fen(Ruleset::Atomic,
+            b"rnb1kbnr/pppp1ppp/8/4p3/4P2q/8/PPPPKPPP/RNBQ1BNR b kq - 0 1");
+
+        assert_eq!(GameOutcome::Ongoing, position.outcome());
+
+        position.do_move(Move::new(Square::H4, Square::F2));
+
+        assert_eq!(GameOutcome::Decisive {
+            winner:      Color::Black,
+            termination: Termination::Explosion,
+        }, position.outcome());
+
+        assert!(position.legal_moves().is_empty());
+    }
+
+    #[test]
+    fn outcome_antichess() {
+        // giving away every piece wins, as does being stalemated
+        let position = Position::from_fen(Ruleset::Antichess,
+            b"8/8/8/8/8/8/8/3K4 w - - 0 1");
+
+        assert_eq!(GameOutcome::Decisive {
+            winner:      Color::Black,
+            termination: Termination::LostAllPieces,
+        }, position.outcome());
+
+        let position = Position::from_fen(Ruleset::Antichess,
+            b"8/8/8/8/8/1p6/1P6/8 w - - 0 1");
+
+        assert_eq!(GameOutcome::Decisive {
+            winner:      Color::White,
+            termination: Termination::Stalemate,
+        }, position.outcome());
+
+        // a bare king can't be checkmated
+        let position = Position::from_fen(Ruleset::Antichess,
+            b"8/8/8/8/8/8/4q3/4K2k b - - 0 1");
+
+        assert_eq!(GameOutcome::Ongoing, position.outcome());
+    }
+
+    #[test]
+    fn outcome_three_check() {
+        let mut position = Position::from_fen(Ruleset::ThreeCheck,
+            b"4k3/8/8/8/8/8/8/4K2R w - - 0 1 +2+0");
+
+        assert_eq!(GameOutcome::Ongoing, position.outcome());
+
+        position.do_move(Move::new(Square::H1, Square::H8));
+
+        assert_eq!(GameOutcome::Decisive {
+            winner:      Color::White,
+            termination: Termination::ThreeChecks,
+        }, position.outcome());
+
+        // the game is over, so black can't answer the check
+        assert!(position.legal_moves().is_empty());
+        assert!(!position.is_legal(Move::new(Square::E8, Square::E7)));
+    }
+
+    #[test]
+    fn outcome_king_of_the_hill() {
+        let position = Position::from_fen(Ruleset::KingOfTheHill,
+            b"8/8/8/3k4/8/8/8/4K3 w - - 0 1");
+
+        assert_eq!(Some(GameOutcome::Decisive {
+            winner:      Color::Black,
+            termination: Termination::KingOfTheHill,
+        }), position.variant_outcome());
+
+        // the center is nothing special in a standard game
+        let position = Position::from_fen(Ruleset::Standard,
+            b"8/8/8/3k4/8/8/8/4K3 w - - 0 1");
+
+        assert_eq!(None, position.variant_outcome());
+        assert_eq!(GameOutcome::Draw { termination: Termination::InsufficientMaterial },
+            position.outcome());
+    }
+
+    #[test]
+    fn outcome_horde() {
+        let position = Position::from_fen(Ruleset::Horde,
+            b"4k3/8/8/8/8/8/8/8 w - - 0 1");
+
+        assert_eq!(GameOutcome::Decisive {
+            winner:      Color::Black,
+            termination: Termination::LostAllPieces,
+        }, position.outcome());
+
+        // the horde can still checkmate black
+        let position = Position::from_fen(Ruleset::Horde,
+            b"4k3/3PP3/3PP3/8/8/8/8/8 b - - 0 1");
+
+        assert_eq!(GameOutcome::Decisive {
+            winner:      Color::White,
+            termination: Termination::Checkmate,
+        }, position.outcome());
+    }
+
+    #[test]
+    fn outcome_racing_kings() {
+        let position = Position::from_fen(Ruleset::RacingKings,
+            b"k7/8/8/8/8/8/8/7K w - - 0 1");
+
+        assert_eq!(GameOutcome::Decisive {
+            winner:      Color::Black,
+            termination: Termination::RacingKings,
+        }, position.outcome());
+
+        // black can't follow the white king to the eighth rank in time
+        let position = Position::from_fen(Ruleset::RacingKings,
+            b"4K3/8/k7/8/8/8/8/8 b - - 0 1");
+
+        assert_eq!(GameOutcome::Decisive {
+            winner:      Color::White,
+            termination: Termination::RacingKings,
+        }, position.outcome());
+
+        // but a black king one step away gets one last move to draw
+        let mut position = Position::from_fen(Ruleset::RacingKings,
+            b"4K3/k7/8/8/8/8/8/8 b - - 0 1");
+
+        assert_eq!(GameOutcome::Ongoing, position.outcome());
+
+        position.do_move(Move::new(Square::A7, Square::A8));
+
+        assert_eq!(GameOutcome::Draw { termination: Termination::RacingKings }, position.outcome());
+
+        // and loses if it does anything else
+        let mut position = Position::from_fen(Ruleset::RacingKings,
+            b"4K3/k7/8/8/8/8/8/8 b - - 0 1");
+
+        position.do_move(Move::new(Square::A7, Square::A6));
+
+        assert_eq!(GameOutcome::Decisive {
+            winner:      Color::White,
+            termination: Termination::RacingKings,
+        }, position.outcome());
+    }
+
+    #[test]
+    fn termination_is_automatic() {
+        assert!( Termination::Checkmate.is_automatic());
+        assert!( Termination::FivefoldRepetition.is_automatic());
+        assert!( Termination::SeventyFiveMoves.is_automatic());
+        assert!(!Termination::ThreefoldRepetition.is_automatic());
+        assert!(!Termination::FiftyMoves.is_automatic());
+    }
+}
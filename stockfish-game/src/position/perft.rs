@@ -0,0 +1,328 @@
+use crate::prelude::*;
+use stockfish_core::prelude::*;
+
+#[allow(clippy::multiple_inherent_impl)]
+impl Position {
+    /// Counts the number of leaf nodes in the tree of legal moves `depth`
+    /// plies deep from this position.
+    ///
+    /// Comparing these counts against published values for well-known
+    /// positions is the standard way to verify move generation, since even the
+    /// most obscure bugs eventually show up as a discrepancy.
+    pub fn perft(&mut self, depth: u8) -> u64 {
+        match depth {
+            0 => 1,
+
+            // there's no need to make the moves in the final ply just to
+            // count them
+            1 => self.legal_moves().len() as u64,
+
+            _ => self.legal_moves().iter().map(|mv| {
+                self.do_move(*mv);
+                let nodes = self.perft(depth - 1);
+                self.undo_move(*mv);
+
+                nodes
+            }).sum(),
+        }
+    }
+
+    /// Performs a [`Position::perft`], breaking down the number of leaf nodes
+    /// by each legal move from this position.
+    ///
+    /// This is invaluable for tracking down move generation bugs, since the
+    /// breakdown can be compared against that of another engine in order to
+    /// narrow in on the subtree where they disagree.
+    pub fn divide(&mut self, depth: u8) -> Vec<(Move, u64)> {
+        self.legal_moves().iter().map(|mv| {
+            self.do_move(*mv);
+            let nodes = self.perft(depth.saturating_sub(1));
+            self.undo_move(*mv);
+
+            (*mv, nodes)
+        }).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const START_POSITION: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+    const KIWIPETE:       &str = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+    const POSITION_3:     &str = "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1";
+    const POSITION_4:     &str = "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1";
+    const POSITION_5:     &str = "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8";
+    const POSITION_6:     &str = "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10";
+
+    fn assert_perft(ruleset: Ruleset, fen: &str, counts: &[u64]) {
+        let mut position = Position::from_fen(ruleset, fen.as_bytes());
+        let     original = position.clone();
+
+        for (depth, &count) in (1..).zip(counts) {
+            assert_eq!(count, position.perft(depth), "depth {depth} of {fen}");
+        }
+
+        assert_eq!(original, position);
+    }
+
+    #[test]
+    fn perft_start_position() {
+        assert_perft(Ruleset::Standard, START_POSITION, &[20, 400, 8_902, 197_281]);
+    }
+
+    #[test]
+    #[ignore = "slow"]
+    fn perft_start_position_deep() {
+        assert_perft(Ruleset::Standard, START_POSITION, &[20, 400, 8_902, 197_281, 4_865_609, 119_060_324]);
+    }
+
+    #[test]
+    fn perft_kiwipete() {
+        assert_perft(Ruleset::Standard, KIWIPETE, &[48, 2_039, 97_862]);
+    }
+
+    #[test]
+    #[ignore = "slow"]
+    fn perft_kiwipete_deep() {
+        assert_perft(Ruleset::Standard, KIWIPETE, &[48, 2_039, 97_862, 4_085_603, 193_690_690]);
+    }
+
+    #[test]
+    fn perft_position_3() {
+        assert_perft(Ruleset::Standard, POSITION_3, &[14, 191, 2_812, 43_238]);
+    }
+
+    #[test]
+    #[ignore = "slow"]
+    fn perft_position_3_deep() {
+        assert_perft(Ruleset::Standard, POSITION_3, &[14, 191, 2_812, 43_238, 674_624, 11_030_083]);
+    }
+
+    #[test]
+    fn perft_position_4() {
+        assert_perft(Ruleset::Standard, POSITION_4, &[6, 264, 9_467]);
+    }
+
+    #[test]
+    #[ignore = "slow"]
+    fn perft_position_4_deep() {
+        assert_perft(Ruleset::Standard, POSITION_4, &[6, 264, 9_467, 422_333, 15_833_292]);
+    }
+
+    #[test]
+    fn perft_position_5() {
+        assert_perft(Ruleset::Standard, POSITION_5, &[44, 1_486, 62_379]);
+    }
+
+    #[test]
+    #[ignore = "slow"]
+    fn perft_position_5_deep() {
+        assert_perft(Ruleset::Standard, POSITION_5, &[44, 1_486, 62_379, 2_103_487, 89_941_194]);
+    }
+
+    #[test]
+    fn perft_position_6() {
+        assert_perft(Ruleset::Standard, POSITION_6, &[46, 2_079, 89_890]);
+    }
+
+    #[test]
+    #[ignore = "slow"]
+    fn perft_position_6_deep() {
+        assert_perft(Ruleset::Standard, POSITION_6, &[46, 2_079, 89_890, 3_894_594, 164_075_551]);
+    }
+
+    #[test]
+    fn perft_chess960() {
+        // the standard starting position is also starting position #518
+        assert_perft(Ruleset::Chess960,
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w HAha - 0 1",
+            &[20, 400, 8_902, 197_281]);
+
+        // other starting positions, by their Scharnagl index; #0 and #959
+        // are mirror images of one another
+        for (index, counts) in [
+            (0,   [20, 400, 9_006, 201_143]),
+            (1,   [20, 400, 8_948, 198_393]),
+            (100, [20, 400, 9_026, 201_178]),
+            (959, [20, 400, 9_006, 201_143]),
+        ] {
+            assert_perft(Ruleset::Chess960, &Position::chess960(index).to_fen(), &counts);
+        }
+
+        // positions from the standard Chess960 perft suite
+        assert_perft(Ruleset::Chess960,
+            "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9",
+            &[21, 528, 12_189]);
+        assert_perft(Ruleset::Chess960,
+            "b1q1rrkb/pppppppp/3nn3/8/P7/1PPP4/4PPPP/BQNNRKRB w GE - 1 9",
+            &[20, 479, 10_471]);
+        assert_perft(Ruleset::Chess960,
+            "qbbnnrkr/2pp2pp/p7/1p2pp2/8/P3PP2/1PPP1KPP/QBBNNR1R w hf - 0 9",
+            &[22, 593, 13_440]);
+        assert_perft(Ruleset::Chess960,
+            "1nbbnrkr/p1p1ppp1/3p4/1p3P1p/3Pq2P/8/PPP1P1P1/QNBBNRKR w HFhf - 0 9",
+            &[28, 1_120, 31_058]);
+    }
+
+    #[test]
+    #[ignore = "slow"]
+    fn perft_chess960_deep() {
+        assert_perft(Ruleset::Chess960,
+            "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9",
+            &[21, 528, 12_189, 326_672, 8_146_062]);
+        assert_perft(Ruleset::Chess960,
+            "b1q1rrkb/pppppppp/3nn3/8/P7/1PPP4/4PPPP/BQNNRKRB w GE - 1 9",
+            &[20, 479, 10_471, 273_318]);
+        assert_perft(Ruleset::Chess960,
+            "qbbnnrkr/2pp2pp/p7/1p2pp2/8/P3PP2/1PPP1KPP/QBBNNR1R w hf - 0 9",
+            &[22, 593, 13_440, 382_958]);
+        assert_perft(Ruleset::Chess960,
+            "1nbbnrkr/p1p1ppp1/3p4/1p3P1p/3Pq2P/8/PPP1P1P1/QNBBNRKR w HFhf - 0 9",
+            &[28, 1_120, 31_058, 1_171_749]);
+    }
+
+    #[test]
+    fn perft_crazyhouse() {
+        // no piece can be captured and dropped back until the fifth ply
+        assert_perft(Ruleset::Crazyhouse,
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR[] w KQkq - 0 1",
+            &[20, 400, 8_902, 197_281]);
+
+        assert_perft(Ruleset::Crazyhouse,
+            "2k5/8/8/8/8/8/8/4K3[QRBNPqrbnp] w - - 0 1",
+            &[301, 75_353]);
+
+        // the promoted queen reverts to a pawn when captured
+        assert_perft(Ruleset::Crazyhouse,
+            "4k3/1Q~6/8/8/4b3/8/Kpp5/8[] b - - 0 1",
+            &[20, 360, 5_445]);
+
+        assert_perft(Ruleset::Crazyhouse,
+            "r1bqk2r/pppp1ppp/2n1p3/4P3/1b1Pn3/2NB1N2/PPP2PPP/R1BQK2R[] b KQkq - 0 1",
+            &[42, 1_347, 58_057]);
+    }
+
+    #[test]
+    #[ignore = "slow"]
+    fn perft_crazyhouse_deep() {
+        assert_perft(Ruleset::Crazyhouse,
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR[] w KQkq - 0 1",
+            &[20, 400, 8_902, 197_281, 4_888_832]);
+
+        assert_perft(Ruleset::Crazyhouse,
+            "4k3/1Q~6/8/8/4b3/8/Kpp5/8[] b - - 0 1",
+            &[20, 360, 5_445, 132_758]);
+
+        assert_perft(Ruleset::Crazyhouse,
+            "r1bqk2r/pppp1ppp/2n1p3/4P3/1b1Pn3/2NB1N2/PPP2PPP/R1BQK2R[] b KQkq - 0 1",
+            &[42, 1_347, 58_057, 2_083_382]);
+    }
+
+    #[test]
+    fn perft_atomic() {
+        // the first captures are made on the third ply and are counted just
+        // as in standard chess, so these only diverge from it once their
+        // explosions change what can follow, on the fourth ply
+        assert_perft(Ruleset::Atomic,
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            &[20, 400, 8_902]);
+
+        assert_perft(Ruleset::Atomic,
+            "rn2kb1r/1pp1p2p/p2q1pp1/3P4/2P3b1/4PN2/PP3PPP/R2QKB1R b KQkq - 0 1",
+            &[40, 1_238, 45_237]);
+
+        assert_perft(Ruleset::Atomic,
+            "rn1qkb1r/p5pp/2p5/3p4/N3P3/5P2/PPP4P/R1BQK3 w Qkq - 0 1",
+            &[28, 833, 23_353]);
+    }
+
+    #[test]
+    #[ignore = "slow"]
+    fn perft_atomic_deep() {
+        assert_perft(Ruleset::Atomic,
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            &[20, 400, 8_902, 197_326]);
+
+        assert_perft(Ruleset::Atomic,
+            "rn2kb1r/1pp1p2p/p2q1pp1/3P4/2P3b1/4PN2/PP3PPP/R2QKB1R b KQkq - 0 1",
+            &[40, 1_238, 45_237, 1_434_825]);
+
+        assert_perft(Ruleset::Atomic,
+            "rn1qkb1r/p5pp/2p5/3p4/N3P3/5P2/PPP4P/R1BQK3 w Qkq - 0 1",
+            &[28, 833, 23_353, 714_499]);
+    }
+
+    #[test]
+    fn perft_antichess() {
+        assert_perft(Ruleset::Antichess,
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w - - 0 1",
+            &[20, 400, 8_067, 153_299]);
+
+        // captures are compulsory, and each one leaves the other side without
+        // any pieces, ending the game; by the fifth ply the only line left is
+        // a3 b6 a4 b5 axb5
+        assert_perft(Ruleset::Antichess,
+            "8/1p6/8/8/8/8/P7/8 w - - 0 1",
+            &[2, 4, 4, 3, 1]);
+    }
+
+    #[test]
+    fn perft_three_check() {
+        // the rook's check from h8 is the third, ending the game
+        assert_perft(Ruleset::ThreeCheck,
+            "4k3/8/8/8/8/8/8/4K2R w - - 0 1 +2+0",
+            &[14, 60, 1_092, 5_965]);
+
+        assert_perft(Ruleset::ThreeCheck,
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1 +2+2",
+            &[48, 2_039, 97_848]);
+    }
+
+    #[test]
+    fn perft_king_of_the_hill() {
+        // no king can reach the center within the first four plies
+        assert_perft(Ruleset::KingOfTheHill,
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            &[20, 400, 8_902, 197_281]);
+
+        // the white king can step straight onto d4
+        assert_perft(Ruleset::KingOfTheHill,
+            "8/8/8/8/8/2K5/8/k7 w - - 0 1",
+            &[7, 14, 90, 374]);
+    }
+
+    #[test]
+    fn perft_horde() {
+        assert_perft(Ruleset::Horde, Ruleset::Horde.start_fen(), &[8, 128, 1_274, 23_310]);
+    }
+
+    #[test]
+    #[ignore = "slow"]
+    fn perft_horde_deep() {
+        assert_perft(Ruleset::Horde, Ruleset::Horde.start_fen(), &[8, 128, 1_274, 23_310, 265_223]);
+    }
+
+    #[test]
+    fn perft_racing_kings() {
+        assert_perft(Ruleset::RacingKings, Ruleset::RacingKings.start_fen(), &[21, 421, 11_264]);
+    }
+
+    #[test]
+    #[ignore = "slow"]
+    fn perft_racing_kings_deep() {
+        assert_perft(Ruleset::RacingKings, Ruleset::RacingKings.start_fen(), &[21, 421, 11_264, 296_242]);
+    }
+
+    #[test]
+    fn divide_start_position() {
+        let mut position = Position::from_fen(Ruleset::Standard, START_POSITION.as_bytes());
+        let     divided  = position.divide(3);
+
+        assert_eq!(20,    divided.len());
+        assert_eq!(8_902, divided.iter().map(|&(_, nodes)| nodes).sum::<u64>());
+
+        assert!(divided.contains(&(Move::new(Square::E2, Square::E4), 600)));
+        assert!(divided.contains(&(Move::new(Square::G1, Square::F3), 440)));
+    }
+}
@@ -0,0 +1,337 @@
+use crate::prelude::*;
+use stockfish_core::prelude::*;
+
+#[allow(clippy::multiple_inherent_impl)]
+impl Position {
+    /// Returns [`true`] if the static exchange evaluation of `mv` is at least
+    /// `threshold`.
+    ///
+    /// This is cheaper than computing [`Position::see`] in full in the common
+    /// case where the outcome is obvious from the pieces involved in the
+    /// initial capture.
+    #[must_use]
+    pub fn see_ge(&self, mv: Move, threshold: Value) -> bool {
+        if mv.move_type() == MoveType::Castling {
+            return Value::ZERO >= threshold;
+        }
+
+        let gain   = self.see_initial_gain(mv);
+        let moving = self.see_moving_value(mv);
+
+        // the opponent can always choose not to recapture, so we can never do
+        // better than the value of the initial capture
+        if gain < threshold {
+            return false;
+        }
+
+        // we can always choose not to recapture, so we can never do worse
+        // than losing the moving piece (unless a recapturing pawn promotes)
+        if gain - moving >= threshold && !Self::is_promotion_rank(mv.destination()) {
+            return true;
+        }
+
+        self.see(mv) >= threshold
+    }
+
+    /// Computes the static exchange evaluation of `mv`: the material balance,
+    /// from the perspective of the moving side, after both sides alternately
+    /// recapture on the destination square with their least valuable piece
+    /// for as long as it's profitable to do so.
+    ///
+    /// X-ray attackers are revealed as the pieces in front of them are traded
+    /// off. Pinned pieces are not allowed to recapture while the piece pinning
+    /// them remains on the board, and kings are only allowed to recapture if
+    /// the square is no longer defended.
+    #[must_use]
+    pub fn see(&self, mv: Move) -> Value {
+        if mv.move_type() == MoveType::Castling {
+            return Value::ZERO;
+        }
+
+        let origin      = mv.origin();
+        let destination = mv.destination();
+        let promotes    = Self::is_promotion_rank(destination);
+
+        let rooks   = self.bitboard_for_piece(Token::Rook)   | self.bitboard_for_piece(Token::Queen);
+        let bishops = self.bitboard_for_piece(Token::Bishop) | self.bitboard_for_piece(Token::Queen);
+
+        let mut occupied = self.bitboard();
+
+        // a dropped piece doesn't vacate any square on its way in
+        if mv.move_type() != MoveType::Drop {
+            occupied ^= origin;
+        }
+
+        if mv.move_type() == MoveType::EnPassant {
+            occupied ^= destination.wrapping_sub(self.turn.direction());
+        }
+
+        let mut attackers = self.attackers_to(destination, occupied);
+        let mut gains     = [Value::ZERO; 32];
+        let mut on_square = self.see_moving_value(mv);
+        let mut color     = self.turn;
+        let mut depth     = 0;
+
+        gains[0] = self.see_initial_gain(mv);
+
+        loop {
+            color      = !color;
+            attackers &= occupied;
+
+            let mut ours = attackers & self.bitboard_for_color(color);
+
+            // pinned pieces may not recapture as long as the piece pinning
+            // them is still on the board
+            if self.pinners(!color).overlaps(occupied) {
+                ours &= !self.blockers_for_king(color);
+            }
+
+            let Some((token, square)) = self.least_valuable(ours) else {
+                break;
+            };
+
+            // the king may only recapture if doing so doesn't move it into
+            // check
+            if token == Token::King && (attackers & self.bitboard_for_color(!color)).is_any() {
+                break;
+            }
+
+            depth += 1;
+
+            let mut gain = on_square - gains[depth - 1];
+
+            on_square = Value::of(token);
+
+            if token == Token::Pawn && promotes {
+                gain      += Value::QUEEN - Value::PAWN;
+                on_square  = Value::QUEEN;
+            }
+
+            gains[depth] = gain;
+
+            // removing the attacker may reveal sliders behind it
+            occupied  ^= square;
+            attackers |= Token::Bishop.attacks(destination, occupied) & bishops;
+            attackers |= Token::Rook  .attacks(destination, occupied) & rooks;
+
+            if depth == gains.len() - 1 {
+                break;
+            }
+        }
+
+        // each side may choose to stop recapturing at any point, so walk back
+        // up the sequence of captures choosing the better of the two
+        while depth > 0 {
+            gains[depth - 1] = -(-gains[depth - 1]).max(gains[depth]);
+            depth -= 1;
+        }
+
+        gains[0]
+    }
+
+    /// Returns the material gained by making `mv`, before any recaptures.
+    fn see_initial_gain(&self, mv: Move) -> Value {
+        let captured = match mv.move_type() {
+            MoveType::EnPassant => Value::PAWN,
+            _                   => self[mv.destination()].map_or(Value::ZERO, |p| Value::of(p.token())),
+        };
+
+        match mv.move_type() {
+            MoveType::Promotion => captured + Value::of(mv.promotion()) - Value::PAWN,
+            _                   => captured,
+        }
+    }
+
+    /// Returns the value of the piece left standing on the destination square
+    /// of `mv`.
+    fn see_moving_value(&self, mv: Move) -> Value {
+        match mv.move_type() {
+            MoveType::Promotion => Value::of(mv.promotion()),
+            MoveType::Drop      => Value::of(mv.dropped()),
+            _                   => self[mv.origin()].map_or(Value::ZERO, |p| Value::of(p.token())),
+        }
+    }
+
+    /// Returns the least valuable piece among `attackers`, along with the
+    /// square it's on.
+    fn least_valuable(&self, attackers: Bitboard) -> Option<(Token, Square)> {
+        Token::iter().find_map(|token| {
+            Option::<Square>::from(attackers & self.bitboard_for_piece(token))
+                .map(|square| (token, square))
+        })
+    }
+
+    fn is_promotion_rank(square: Square) -> bool {
+        square.rank() == Rank::_1 || square.rank() == Rank::_8
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn see_undefended() {
+        let position = Position::from_fen(Ruleset::Standard,
+            b"1k1r4/1pp4p/p7/4p3/8/P5P1/1PP4P/2K1R3 w - - 0 1");
+
+        assert_eq!(Value::PAWN, position.see(Move::new(Square::E1, Square::E5)));
+    }
+
+    #[test]
+    fn see_defended_by_pawn() {
+        // NxP, PxN
+        let position = Position::from_fen(Ruleset::Standard,
+            b"4k3/8/3p4/4p3/8/5N2/8/4K3 w - - 0 1");
+
+        assert_eq!(Value::PAWN - Value::KNIGHT, position.see(Move::new(Square::F3, Square::E5)));
+    }
+
+    #[test]
+    fn see_xray() {
+        // RxP, RxR, RxR: the rook behind the first one gets the last word
+        let position = Position::from_fen(Ruleset::Standard,
+            b"3rk3/8/8/3p4/8/8/3R4/3RK3 w - - 0 1");
+
+        assert_eq!(Value::PAWN, position.see(Move::new(Square::D2, Square::D5)));
+
+        let position = Position::from_fen(Ruleset::Standard,
+            b"3rk3/8/8/3p4/8/8/3R4/4K3 w - - 0 1");
+
+        assert_eq!(Value::PAWN - Value::ROOK, position.see(Move::new(Square::D2, Square::D5)));
+
+        // the queen backs up the bishop along the diagonal
+        let position = Position::from_fen(Ruleset::Standard,
+            b"4k3/6b1/8/4p3/8/2B5/1Q6/4K3 w - - 0 1");
+
+        assert_eq!(Value::PAWN, position.see(Move::new(Square::C3, Square::E5)));
+
+        let position = Position::from_fen(Ruleset::Standard,
+            b"4k3/6b1/8/4p3/8/2B5/8/4K3 w - - 0 1");
+
+        assert_eq!(Value::PAWN - Value::BISHOP, position.see(Move::new(Square::C3, Square::E5)));
+    }
+
+    #[test]
+    fn see_recaptures_with_least_valuable() {
+        // NxN, then black recaptures with the pawn rather than the queen
+        let position = Position::from_fen(Ruleset::Standard,
+            b"4k3/3q4/4p3/3n4/8/4N3/8/4K3 w - - 0 1");
+
+        assert_eq!(Value::ZERO, position.see(Move::new(Square::E3, Square::D5)));
+    }
+
+    #[test]
+    fn see_king_cannot_recapture_defended() {
+        // the black king can't recapture since the white king defends d2
+        let position = Position::from_fen(Ruleset::Standard,
+            b"8/8/8/8/8/2k5/3p4/3RK3 w - - 0 1");
+
+        assert_eq!(Value::PAWN, position.see(Move::new(Square::D1, Square::D2)));
+
+        let position = Position::from_fen(Ruleset::Standard,
+            b"8/8/8/8/8/2k5/3p4/3R2K1 w - - 0 1");
+
+        assert_eq!(Value::PAWN - Value::ROOK, position.see(Move::new(Square::D1, Square::D2)));
+    }
+
+    #[test]
+    fn see_pinned_recapture() {
+        // the knight on e6 is pinned to its king and may not recapture
+        let position = Position::from_fen(Ruleset::Standard,
+            b"4k3/8/4n3/8/3p4/8/5B2/4R1K1 w - - 0 1");
+
+        assert_eq!(Value::PAWN, position.see(Move::new(Square::F2, Square::D4)));
+
+        let position = Position::from_fen(Ruleset::Standard,
+            b"4k3/8/4n3/8/3p4/8/5B2/6K1 w - - 0 1");
+
+        assert_eq!(Value::PAWN - Value::BISHOP, position.see(Move::new(Square::F2, Square::D4)));
+    }
+
+    #[test]
+    fn see_en_passant() {
+        let position = Position::from_fen(Ruleset::Standard,
+            b"4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1");
+
+        assert_eq!(Value::PAWN, position.see(Move::new_en_passant(Square::E5, Square::D6)));
+
+        let position = Position::from_fen(Ruleset::Standard,
+            b"4k3/2p5/8/3pP3/8/8/8/4K3 w - d6 0 1");
+
+        assert_eq!(Value::ZERO, position.see(Move::new_en_passant(Square::E5, Square::D6)));
+    }
+
+    #[test]
+    fn see_promotion() {
+        // promoting on an undefended square gains a queen for a pawn
+        let position = Position::from_fen(Ruleset::Standard,
+            b"8/4P3/8/8/8/8/k7/4K3 w - - 0 1");
+
+        assert_eq!(Value::QUEEN - Value::PAWN,
+            position.see(Move::new_promote_queen(Square::E7, Square::E8)));
+
+        // capturing and promoting, only to have the promoted piece recaptured
+        let position = Position::from_fen(Ruleset::Standard,
+            b"3rr1k1/4P3/8/8/8/8/8/4K3 w - - 0 1");
+
+        assert_eq!(Value::ROOK - Value::PAWN,
+            position.see(Move::new_promote_queen(Square::E7, Square::D8)));
+        assert_eq!(Value::ROOK - Value::PAWN,
+            position.see(Move::new_promote_knight(Square::E7, Square::D8)));
+    }
+
+    #[test]
+    fn see_recapture_promotes() {
+        // black's pawn recaptures on the first rank and promotes
+        let position = Position::from_fen(Ruleset::Standard,
+            b"4k3/8/8/8/8/8/2p5/1n1RK3 w - - 0 1");
+
+        assert_eq!(Value::KNIGHT - Value::ROOK - (Value::QUEEN - Value::PAWN),
+            position.see(Move::new(Square::D1, Square::B1)));
+    }
+
+    #[test]
+    fn see_castling() {
+        let position = Position::from_fen(Ruleset::Standard,
+            b"4k3/8/8/8/8/8/8/4K2R w K - 0 1");
+
+        assert_eq!(Value::ZERO, position.see(Move::new_castling(Square::E1, Square::H1)));
+    }
+
+    #[test]
+    fn see_ge() {
+        let position = Position::from_fen(Ruleset::Standard,
+            b"4k3/8/3p4/4p3/8/5N2/8/4K3 w - - 0 1");
+
+        let mv = Move::new(Square::F3, Square::E5);
+
+        assert!( position.see_ge(mv, Value::PAWN - Value::KNIGHT));
+        assert!(!position.see_ge(mv, Value::ZERO));
+
+        let position = Position::from_fen(Ruleset::Standard,
+            b"4k3/8/8/4p3/8/5N2/8/4K3 w - - 0 1");
+
+        assert!( position.see_ge(mv, Value::PAWN));
+        assert!(!position.see_ge(mv, Value::PAWN + Value::new(1)));
+    }
+
+    #[test]
+    fn see_ge_agrees_with_see() {
+        for fen in [
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+            "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+        ] {
+            let position = Position::from_fen(Ruleset::Standard, fen.as_bytes());
+
+            for mv in &position.legal_moves() {
+                let see = position.see(*mv);
+
+                assert!( position.see_ge(*mv, see),                 "{mv:?} in {fen}");
+                assert!(!position.see_ge(*mv, see + Value::new(1)), "{mv:?} in {fen}");
+            }
+        }
+    }
+}
@@ -0,0 +1,164 @@
+use stockfish_core::prelude::*;
+use stockfish_core::hash::{Key, ZOBRIST};
+
+/// The parts of a [`Position`](crate::position::Position) that can't be
+/// recovered by simply reversing a move on the board.
+///
+/// A copy of this is pushed onto the position's history every time a move is
+/// made, and popped back off when it's undone.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) struct State {
+    // irreversible board state
+    pub(crate) castling_rights: CastlingRights,
+    pub(crate) en_passant:      Option<Square>,
+    pub(crate) halfmoves:       u8,
+
+    // the number of plies since the last null move, beyond which repetitions
+    // can't be trusted
+    pub(crate) plies_from_null: u16,
+
+    // the piece captured by the move that led to this state, if any, and
+    // whether it had been promoted (for crazyhouse)
+    pub(crate) captured:          Option<Piece>,
+    pub(crate) captured_promoted: bool,
+
+    // the squares cleared by the explosion following a capture in atomic
+    // chess, and the pieces that stood on them in square order; at most the
+    // capturing piece and its eight neighbors can be caught in the blast
+    pub(crate) blast:    Bitboard,
+    pub(crate) exploded: [Option<Piece>; 9],
+
+    // the number of checks given so far by each side, which are only counted
+    // in three-check
+    pub(crate) checks: [u8; Color::COUNT],
+
+    // the distance in plies to an earlier occurrence of this position, or
+    // zero if there isn't one; negative if that position was itself a
+    // repetition
+    pub(crate) repetition: i16,
+
+    // hash keys for the position
+    pub(crate) keys: Keys,
+
+    // check state, derived from the board and refreshed whenever it changes
+    pub(crate) checkers: Bitboard,
+    pub(crate) blockers: [Bitboard; Color::COUNT],
+    pub(crate) pinners:  [Bitboard; Color::COUNT],
+
+    // the squares from which each token of the side to move would give check
+    // to the enemy king
+    pub(crate) check_squares: [Bitboard; Token::COUNT],
+}
+
+impl State {
+    pub(crate) fn empty() -> Self {
+        Self {
+            castling_rights: CastlingRights::NONE,
+            en_passant:      None,
+            halfmoves:       0,
+            plies_from_null: 0,
+
+            captured:          None,
+            captured_promoted: false,
+            repetition:        0,
+
+            blast:    Bitboard::EMPTY,
+            exploded: [None; 9],
+
+            checks: [0; Color::COUNT],
+
+            keys: Keys::empty(),
+
+            checkers: Bitboard::EMPTY,
+            blockers: [Bitboard::EMPTY; Color::COUNT],
+            pinners:  [Bitboard::EMPTY; Color::COUNT],
+
+            check_squares: [Bitboard::EMPTY; Token::COUNT],
+        }
+    }
+}
+
+/// The Zobrist keys identifying a position, which are updated incrementally as
+/// pieces are placed on and removed from the board.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Keys {
+    // every piece on the board, along with the castling rights, the en
+    // passant square, the side to move, the pieces in each pocket, and the
+    // checks given by each side
+    pub(crate) position: Key,
+
+    // every pawn on the board
+    pub(crate) pawns: Key,
+
+    // the number of each type of piece on the board, regardless of where
+    pub(crate) material: Key,
+
+    // every non-pawn piece of each color on the board
+    pub(crate) non_pawns: [Key; Color::COUNT],
+}
+
+impl Keys {
+    // an empty board still has a (lack of) castling rights to hash
+    pub(crate) fn empty() -> Self {
+        Self {
+            position:  ZOBRIST.castling_key(CastlingRights::NONE),
+            pawns:     ZOBRIST.no_pawns_key(),
+            material:  Key::default(),
+            non_pawns: [Key::default(); Color::COUNT],
+        }
+    }
+
+    /// Toggles the presence of a `piece` on the given `square`.
+    #[inline]
+    pub(crate) fn toggle_piece(&mut self, piece: Piece, square: Square) {
+        let key = ZOBRIST.piece_square_key(piece, square);
+
+        self.position ^= key;
+
+        if piece.token() == Token::Pawn {
+            self.pawns ^= key;
+        } else {
+            self.non_pawns[piece.color()] ^= key;
+        }
+    }
+
+    /// Toggles whether or not there are at least `count + 1` of the given
+    /// `piece` on the board.
+    #[inline]
+    pub(crate) fn toggle_material(&mut self, piece: Piece, count: u8) {
+        self.material ^= ZOBRIST.material_key(piece, count);
+    }
+
+    /// Toggles whether or not there are at least `count + 1` of the given
+    /// `piece` in its owner's pocket.
+    #[inline]
+    pub(crate) fn toggle_pocket(&mut self, piece: Piece, count: u8) {
+        self.position ^= ZOBRIST.pocket_key(piece, count);
+    }
+
+    /// Toggles whether or not `color` has given at least `count + 1` checks.
+    #[inline]
+    pub(crate) fn toggle_checks(&mut self, color: Color, count: u8) {
+        self.position ^= ZOBRIST.checks_key(color, count);
+    }
+
+    /// Toggles the presence of the given set of castling `rights`.
+    #[inline]
+    pub(crate) fn toggle_castling(&mut self, rights: CastlingRights) {
+        self.position ^= ZOBRIST.castling_key(rights);
+    }
+
+    /// Toggles the presence of an `en_passant` square, if there is one.
+    #[inline]
+    pub(crate) fn toggle_en_passant(&mut self, en_passant: Option<Square>) {
+        if let Some(square) = en_passant {
+            self.position ^= ZOBRIST.en_passant_key(square.file());
+        }
+    }
+
+    /// Toggles the side to move.
+    #[inline]
+    pub(crate) fn toggle_side(&mut self) {
+        self.position ^= ZOBRIST.side_key();
+    }
+}
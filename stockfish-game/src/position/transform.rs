@@ -0,0 +1,266 @@
+use crate::prelude::*;
+use stockfish_core::prelude::*;
+
+#[allow(clippy::multiple_inherent_impl)]
+impl Position {
+    /// Returns the same position from the other side of the board: every
+    /// piece (including those in each pocket) swaps color and is reflected
+    /// top-to-bottom, and the other side is to move.
+    ///
+    /// A flipped position is exactly as good for the side to move as the
+    /// original, which makes this useful for testing the symmetry of an
+    /// evaluation. The move history is not carried over.
+    ///
+    /// Horde and racing kings positions can't be flipped, so the position is
+    /// returned as it is (again without its move history) instead. Use
+    /// [`Position::try_flipped`] to tell these apart.
+    #[must_use]
+    pub fn flipped(&self) -> Self {
+        self.try_flipped()
+            .unwrap_or_else(|| self.transformed(core::convert::identity, false))
+    }
+
+    /// Returns the same position from the other side of the board, as
+    /// described by [`Position::flipped`].
+    ///
+    /// Returns [`None`] in horde and racing kings, whose rules treat the two
+    /// sides differently: there's no such thing as a black horde, or a race
+    /// toward the first rank.
+    #[must_use]
+    pub fn try_flipped(&self) -> Option<Self> {
+        self.is_flippable().then(|| self.transformed(Square::flip_rank, true))
+    }
+
+    /// Returns the same position reflected left-to-right, with every piece
+    /// keeping its color.
+    ///
+    /// Castling rights are mirrored along with everything else, so the king
+    /// and rook swap sides. Since a standard position with castling rights
+    /// mirrors into one that could only arise in Chess960, such positions are
+    /// given the [`Ruleset::Chess960`] ruleset. Positions in any other variant
+    /// keep their ruleset. The move history is not carried over.
+    ///
+    /// Castling itself is *not* mirrored: the king and rook still finish on
+    /// the usual squares, so castling long from d1 puts the king on g1 rather
+    /// than on f1. The mirrored position's castling moves are therefore not
+    /// the mirror images of the original's, and its perft may differ.
+    #[must_use]
+    pub fn mirrored(&self) -> Self {
+        self.transformed(Square::flip_file, false)
+    }
+
+    /// Returns whether the rules of the position treat both sides alike, so
+    /// that it can be flipped.
+    const fn is_flippable(&self) -> bool {
+        !matches!(self.ruleset, Ruleset::Horde | Ruleset::RacingKings)
+    }
+
+    /// Rebuilds the position with every square transformed by `transform`,
+    /// swapping the colors of everything if `swap` is set.
+    fn transformed(&self, transform: fn(Square) -> Square, swap: bool) -> Self {
+        let color = |color: Color| if swap { !color } else { color };
+
+        let mut board    = Board::EMPTY;
+        let mut pockets  = [0; Piece::COUNT];
+        let mut promoted = Bitboard::EMPTY;
+
+        for (square, piece) in self.board.iter() {
+            board[transform(square)] = Some(color(piece.color()) | piece.token());
+        }
+
+        for piece in Piece::iter() {
+            pockets[color(piece.color()) | piece.token()] = self.pockets[piece];
+        }
+
+        for square in self.promoted {
+            promoted |= transform(square);
+        }
+
+        let mut castling: [Option<CastlingPath>; CastlingVariety::COUNT] = Default::default();
+
+        for path in self.castling_paths.iter().flatten() {
+            if !self.can_castle(path.rights()) {
+                continue;
+            }
+
+            let transformed = CastlingPath::new(
+                color(path.color()),
+                transform(path.king_origin()).file(),
+                transform(path.rook_origin()).file(),
+            );
+
+            if let Some(transformed) = transformed {
+                castling[transformed.variety()] = Some(transformed);
+            }
+        }
+
+        let ruleset = if !swap
+            && self.ruleset == Ruleset::Standard
+            && castling.iter().any(Option::is_some)
+        {
+            Ruleset::Chess960
+        } else {
+            self.ruleset
+        };
+
+        // the fullmove number is kept the same, even if the side to move
+        // changes
+        let turn = color(self.turn);
+        let ply  = (self.ply & !1) | u16::from(turn.is_black());
+
+        Self::from_parts(
+            ruleset,
+            board,
+            turn,
+            castling,
+            self.state.en_passant.map(transform),
+            self.state.halfmoves,
+            ply,
+        ).with_pockets(pockets, promoted)
+            .with_checks(Color::VARIANTS.map(|c| self.state.checks[color(c)]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flipped() {
+        let position = Position::from_fen(Ruleset::Standard,
+            b"rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+
+        assert_eq!("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR b KQkq - 0 1",
+            position.flipped().to_fen());
+
+        let position = Position::from_fen(Ruleset::Standard,
+            b"r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1");
+
+        assert_eq!("r3k2r/pppbbppp/2n2q1P/1P2p3/3pn3/BN2PNP1/P1PPQPB1/R3K2R b KQkq - 0 1",
+            position.flipped().to_fen());
+
+        // the en passant square and castling rights follow along
+        let position = Position::from_fen(Ruleset::Standard,
+            b"rnbqkbnr/ppp2ppp/8/3pP3/8/8/PPPP1PPP/RNBQKB1R w KQq d6 4 7");
+
+        assert_eq!("rnbqkb1r/pppp1ppp/8/8/3Pp3/8/PPP2PPP/RNBQKBNR b Qkq d3 4 7",
+            position.flipped().to_fen());
+    }
+
+    #[test]
+    fn flipped_chess960() {
+        let position = Position::from_fen(Ruleset::Chess960,
+            b"bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9");
+
+        assert_eq!("bq1bnrkr/npp1p1pp/p2p4/5p2/2P5/3PPN2/PP3PPP/BQNB1RKR b HFhf - 2 9",
+            position.flipped().to_fen());
+    }
+
+    #[test]
+    fn flipped_three_check() {
+        // each side keeps the checks it has given
+        let position = Position::from_fen(Ruleset::ThreeCheck,
+            b"4k3/8/8/8/8/8/8/4K2R w - - 0 1 +2+1");
+
+        assert_eq!("4k2r/8/8/8/8/8/8/4K3 b - - 0 1 +1+2", position.flipped().to_fen());
+    }
+
+    #[test]
+    fn flipped_twice() {
+        for fen in [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3",
+        ] {
+            let position = Position::from_fen(Ruleset::Standard, fen.as_bytes());
+
+            assert_eq!(fen,            position.flipped().flipped().to_fen());
+            assert_eq!(position.key(), position.flipped().flipped().key());
+            assert_ne!(position.key(), position.flipped().key());
+        }
+    }
+
+    #[test]
+    fn flipped_perft() {
+        let mut position = Position::from_fen(Ruleset::Standard,
+            b"r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1");
+
+        assert_eq!(position.perft(3), position.flipped().perft(3));
+    }
+
+    #[test]
+    fn flipped_asymmetric_variants() {
+        for ruleset in [Ruleset::Horde, Ruleset::RacingKings] {
+            let position = Position::from_fen(ruleset, ruleset.start_fen().as_bytes());
+
+            assert_eq!(None, position.try_flipped());
+        }
+    }
+
+    #[test]
+    fn flipped_asymmetric_variants_unchanged() {
+        for ruleset in [Ruleset::Horde, Ruleset::RacingKings] {
+            let position = Position::from_fen(ruleset, ruleset.start_fen().as_bytes());
+
+            assert_eq!(ruleset.start_fen(), position.flipped().to_fen());
+            assert_eq!(position.key(),      position.flipped().key());
+        }
+    }
+
+    #[test]
+    fn try_flipped() {
+        let position = Position::from_fen(Ruleset::Standard,
+            b"rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+
+        assert_eq!(Some(position.flipped()), position.try_flipped());
+    }
+
+    #[test]
+    fn mirrored() {
+        let position = Position::from_fen(Ruleset::Standard,
+            b"3k4/8/8/8/8/2P5/8/4K3 b - - 0 1");
+
+        assert_eq!("4k3/8/8/8/8/5P2/8/3K4 b - - 0 1", position.mirrored().to_fen());
+
+        // the en passant square follows along
+        let position = Position::from_fen(Ruleset::Standard,
+            b"4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1");
+
+        assert_eq!("3k4/8/8/3Pp3/8/8/8/3K4 w - e6 0 1", position.mirrored().to_fen());
+    }
+
+    #[test]
+    fn mirrored_castling() {
+        let position = Position::from_fen(Ruleset::Standard,
+            b"r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1");
+
+        let mirrored = position.mirrored();
+
+        // castling rights move to the other side of the board, although the
+        // king and rook still end up on the usual squares when castling
+        assert_eq!(Ruleset::Chess960, mirrored.ruleset());
+        assert_eq!("r2k3r/8/8/8/8/8/8/R2K3R w HAha - 0 1", mirrored.to_fen());
+        assert!(mirrored.legal_moves().contains(&Move::new_castling(Square::D1, Square::H1)));
+        assert!(mirrored.legal_moves().contains(&Move::new_castling(Square::D1, Square::A1)));
+
+        // so castling long from d1 ends with the king on g1, not f1
+        let mut mirrored = mirrored;
+
+        mirrored.do_move(Move::new_castling(Square::D1, Square::H1));
+
+        assert_eq!(Some(Square::G1), mirrored.king_square(Color::White));
+    }
+
+    #[test]
+    fn mirrored_castling_variant() {
+        let position = Position::from_fen(Ruleset::Crazyhouse,
+            b"r3k2r/8/8/8/8/8/8/R3K2R[Nn] w KQkq - 0 1");
+
+        let mirrored = position.mirrored();
+
+        // only standard chess needs to become Chess960 to castle like this
+        assert_eq!(Ruleset::Crazyhouse, mirrored.ruleset());
+        assert!(mirrored.legal_moves().contains(&Move::new_castling(Square::D1, Square::H1)));
+        assert!(mirrored.legal_moves().contains(&Move::new_drop(Token::Knight, Square::E4)));
+    }
+}
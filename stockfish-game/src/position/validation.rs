@@ -0,0 +1,332 @@
+use crate::prelude::*;
+use stockfish_core::prelude::*;
+
+use core::fmt::{Display, Formatter, Result as FmtResult};
+
+/// A reason a [`Position`] can't have been reached in a legal game of chess,
+/// as reported by [`Position::validate`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum PositionError {
+    /// A side doesn't have the `expected` number of kings. That's one, except
+    /// that any number is allowed in antichess, and the white horde has none
+    /// in horde.
+    KingCount { color: Color, expected: u8, count: u8 },
+
+    /// A pawn is on the first or eighth rank, where it could never have
+    /// stayed. The white horde starts with pawns on the first rank in horde.
+    PawnOnBackRank { square: Square },
+
+    /// The side that just moved left its own king in check.
+    OppositeCheck,
+
+    /// The king of the side to move is attacked by more pieces, or a
+    /// combination of pieces, that no single move could have uncovered. No
+    /// check is possible at all in racing kings.
+    ImpossibleCheck { count: u8 },
+
+    /// A castling right is held even though the king or rook has left its
+    /// starting square.
+    InvalidCastling { variety: CastlingVariety },
+
+    /// No pawn could have just advanced two squares past the en passant
+    /// square.
+    InvalidEnPassant { square: Square },
+}
+
+#[allow(clippy::multiple_inherent_impl)]
+impl Position {
+    /// Checks whether the position could have been reached in a legal game of
+    /// chess, returning every problem found if not.
+    ///
+    /// This is intended for positions supplied by users, such as those built
+    /// up one piece at a time with [`Position::emplace`]. It makes no
+    /// assumptions about the position's check information being up to date.
+    pub fn validate(&self) -> Result<(), Vec<PositionError>> {
+        let mut errors = Vec::new();
+
+        // kings are ordinary pieces in antichess, so there may be any number
+        // of them and none of them can be in check
+        let royal = self.ruleset != Ruleset::Antichess;
+
+        for color in Color::iter().filter(|_| royal) {
+            let count    = self.count_by_token[color | Token::King];
+            let expected = u8::from(!(self.ruleset == Ruleset::Horde && color.is_white()));
+
+            if count != expected {
+                errors.push(PositionError::KingCount { color, expected, count });
+            }
+        }
+
+        let back_ranks = Bitboard::RANK_1 | Bitboard::RANK_8;
+        let horde      = if self.ruleset == Ruleset::Horde {
+            self.bb_by_color[Color::White] & Bitboard::RANK_1
+        } else {
+            Bitboard::EMPTY
+        };
+
+        for square in self.bb_by_piece[Token::Pawn] & back_ranks & !horde {
+            errors.push(PositionError::PawnOnBackRank { square });
+        }
+
+        // check can only be evaluated when there's exactly one king to be in
+        // it, so only look once the kings have been found to be in order
+        if royal && errors.iter().all(|e| !matches!(e, PositionError::KingCount { .. })) {
+            if self.attackers_of_king(!self.turn).is_any() {
+                errors.push(PositionError::OppositeCheck);
+            }
+
+            let checkers = self.attackers_of_king(self.turn);
+
+            if !self.is_possible_check(checkers) {
+                #[allow(clippy::cast_possible_truncation)]
+                let count = checkers.count() as u8;
+
+                errors.push(PositionError::ImpossibleCheck { count });
+            }
+        }
+
+        for variety in CastlingVariety::iter() {
+            if self.can_castle(variety.rights()) && !self.has_castling_pieces(variety) {
+                errors.push(PositionError::InvalidCastling { variety });
+            }
+        }
+
+        // an en passant square no pawn can capture onto is harmless, so only
+        // the double push that would have left it behind is checked
+        if let Some(square) = self.state.en_passant.filter(|&s| !self.is_double_push_square(s)) {
+            errors.push(PositionError::InvalidEnPassant { square });
+        }
+
+        if errors.is_empty() { Ok(()) } else { Err(errors) }
+    }
+
+    /// Returns the enemy pieces attacking the king of the given `color`.
+    pub(super) fn attackers_of_king(&self, color: Color) -> Bitboard {
+        self.king_square(color).map_or(Bitboard::EMPTY, |king| {
+            // kings may be captured like any other piece in antichess
+            if self.ruleset == Ruleset::Antichess {
+                return Bitboard::EMPTY;
+            }
+
+            // in atomic chess a king touching the enemy king can't be
+            // captured, since the enemy king would go up in the same explosion
+            if self.ruleset == Ruleset::Atomic
+                && Token::King.moves(king).overlaps(self.bitboard_for_token(!color | Token::King))
+            {
+                return Bitboard::EMPTY;
+            }
+
+            self.attackers_to(king, self.bb_all) & self.bb_by_color[!color]
+        })
+    }
+
+    /// Returns [`true`] if the king could be checked by every one of the
+    /// `checkers` at once.
+    ///
+    /// A single move can only give check directly with the piece moved and
+    /// by discovery with a slider behind it, so there can never be more than
+    /// two checkers, and one of a pair of checkers must be a slider. In
+    /// racing kings, no move may give check in the first place.
+    fn is_possible_check(&self, checkers: Bitboard) -> bool {
+        if self.ruleset == Ruleset::RacingKings {
+            return checkers.is_empty();
+        }
+
+        let sliders = self.bb_by_piece[Token::Bishop]
+            | self.bb_by_piece[Token::Rook]
+            | self.bb_by_piece[Token::Queen];
+
+        !checkers.is_many() || (checkers.count() == 2 && checkers.overlaps(sliders))
+    }
+}
+
+impl Display for PositionError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        let name = |color: Color| if color.is_white() { "white" } else { "black" };
+
+        match *self {
+            Self::KingCount { color, expected, count } => {
+                let kings = if expected == 1 { "king" } else { "kings" };
+
+                write!(f, "expected {expected} {} {kings}, found {count}", name(color))
+            },
+
+            Self::PawnOnBackRank { square } =>
+                write!(f, "pawn on back rank at {square:?}"),
+
+            Self::OppositeCheck =>
+                f.write_str("the side not to move is in check"),
+
+            Self::ImpossibleCheck { count } =>
+                write!(f, "impossible check by {count} pieces"),
+
+            Self::InvalidCastling { variety } =>
+                write!(f, "no king and rook to castle with for {variety:?}"),
+
+            Self::InvalidEnPassant { square } =>
+                write!(f, "impossible en passant square {square:?}"),
+        }
+    }
+}
+
+impl std::error::Error for PositionError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validate_legal() {
+        for fen in [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3",
+
+            // a double check by a knight and the rook it uncovered
+            "4k3/8/3N4/8/8/8/8/4RK2 b - - 0 1",
+        ] {
+            let position = Position::from_fen(Ruleset::Standard, fen.as_bytes());
+
+            assert_eq!(Ok(()), position.validate(), "{fen}");
+        }
+    }
+
+    #[test]
+    fn validate_kings() {
+        let position = Position::from_fen(Ruleset::Standard,
+            b"4k2k/8/8/8/8/8/8/8 w - - 0 1");
+
+        assert_eq!(Err(vec![
+            PositionError::KingCount { color: Color::White, expected: 1, count: 0 },
+            PositionError::KingCount { color: Color::Black, expected: 1, count: 2 },
+        ]), position.validate());
+
+        // but kings are ordinary pieces in antichess, so can't be in check
+        // either
+        let position = Position::from_fen(Ruleset::Antichess,
+            b"4k2k/8/8/8/8/8/8/7R w - - 0 1");
+
+        assert_eq!(Ok(()), position.validate());
+    }
+
+    #[test]
+    fn validate_pawns_on_back_rank() {
+        let position = Position::from_fen(Ruleset::Standard,
+            b"P3k3/8/8/8/8/8/8/4K2p w - - 0 1");
+
+        assert_eq!(Err(vec![
+            PositionError::PawnOnBackRank { square: Square::H1 },
+            PositionError::PawnOnBackRank { square: Square::A8 },
+        ]), position.validate());
+    }
+
+    #[test]
+    fn validate_horde() {
+        let position = Position::from_fen(Ruleset::Horde,
+            b"4k3/8/8/8/8/8/8/PPPPPPPP w - - 0 1");
+
+        assert_eq!(Ok(()), position.validate());
+
+        // the horde may not have a king
+        let position = Position::from_fen(Ruleset::Horde,
+            b"4k3/8/8/8/8/8/8/PPPPKPPP w - - 0 1");
+
+        assert_eq!(Err(vec![PositionError::KingCount { color: Color::White, expected: 0, count: 1 }]),
+            position.validate());
+
+        assert_eq!("expected 0 white kings, found 1",
+            PositionError::KingCount { color: Color::White, expected: 0, count: 1 }.to_string());
+    }
+
+    #[test]
+    fn validate_racing_kings() {
+        let position = Position::from_fen(Ruleset::RacingKings,
+            b"8/8/8/8/8/k7/8/R6K b - - 0 1");
+
+        assert_eq!(Err(vec![PositionError::ImpossibleCheck { count: 1 }]), position.validate());
+    }
+
+    #[test]
+    fn validate_opposite_check() {
+        let position = Position::from_fen(Ruleset::Standard,
+            b"4k2R/8/8/8/8/8/8/4K3 w - - 0 1");
+
+        assert_eq!(Err(vec![PositionError::OppositeCheck]), position.validate());
+    }
+
+    #[test]
+    fn validate_impossible_check() {
+        // two knights can't give check at once
+        let position = Position::from_fen(Ruleset::Standard,
+            b"4k3/8/3N1N2/8/8/8/8/4K3 b - - 0 1");
+
+        assert_eq!(Err(vec![PositionError::ImpossibleCheck { count: 2 }]), position.validate());
+
+        // nor can three pieces of any kind
+        let position = Position::from_fen(Ruleset::Standard,
+            b"4k3/8/3N4/8/Q7/8/8/4RK2 b - - 0 1");
+
+        assert_eq!(Err(vec![PositionError::ImpossibleCheck { count: 3 }]), position.validate());
+    }
+
+    #[test]
+    fn validate_castling() {
+        let mut position = Position::from_fen(Ruleset::Standard,
+            b"r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1");
+
+        let _ = position.remove(Square::H1);
+        position.emplace(Piece::WhiteRook, Square::G1);
+
+        let _ = position.remove(Square::E8);
+        position.emplace(Piece::BlackKing, Square::F8);
+
+        assert_eq!(Err(vec![
+            PositionError::InvalidCastling { variety: CastlingVariety::WhiteKingside },
+            PositionError::InvalidCastling { variety: CastlingVariety::BlackKingside },
+            PositionError::InvalidCastling { variety: CastlingVariety::BlackQueenside },
+        ]), position.validate());
+    }
+
+    #[test]
+    fn validate_en_passant() {
+        let mut position = Position::from_fen(Ruleset::Standard,
+            b"4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1");
+
+        let _ = position.remove(Square::D5);
+        position.emplace(Piece::BlackPawn, Square::D7);
+
+        assert_eq!(Err(vec![PositionError::InvalidEnPassant { square: Square::D6 }]),
+            position.validate());
+    }
+
+    #[test]
+    fn validate_atomic_touching_kings() {
+        // the rook attacks the white king, but can't capture it without
+        // blowing up its own king
+        let position = Position::from_fen(Ruleset::Atomic, b"8/8/8/8/8/4k3/4K3/4r3 b - - 0 1");
+
+        assert_eq!(Ok(()), position.validate());
+    }
+
+    #[test]
+    fn validate_empty_board() {
+        let mut position = Position::empty(Ruleset::Standard);
+
+        position.emplace(Piece::WhiteKing, Square::E1);
+        position.emplace(Piece::BlackKing, Square::E8);
+        position.emplace(Piece::WhitePawn, Square::E2);
+
+        assert_eq!(Ok(()), position.validate());
+
+        position.emplace(Piece::WhiteRook, Square::E7);
+
+        // it's white's turn, but the black king is in check
+        assert_eq!(Err(vec![PositionError::OppositeCheck]), position.validate());
+    }
+
+    #[test]
+    fn position_error_display() {
+        assert_eq!("expected 1 white king, found 0",
+            PositionError::KingCount { color: Color::White, expected: 1, count: 0 }.to_string());
+    }
+}
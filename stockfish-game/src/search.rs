@@ -0,0 +1,925 @@
+use crate::prelude::*;
+use stockfish_core::prelude::*;
+
+use std::time::{Duration, Instant};
+
+pub use crate::value::MAX_PLY;
+
+/// The number of nodes searched between checks of the clock.
+const CLOCK_INTERVAL: u64 = 1024;
+
+/// The margin by which a capture in the quiescence search must be able to
+/// raise the static evaluation above alpha to be worth searching.
+const DELTA_MARGIN: Value = Value::new(200);
+
+/// Limits on how far a search may go. Any limit that isn't set is ignored,
+/// and a search without any limits at all continues until
+/// [`MAX_PLY`] is reached.
+///
+/// The first iteration is always searched to completion regardless of the
+/// limits, so that there's always a move to be played.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Limits {
+    /// The greatest depth to search to, in plies.
+    pub depth: Option<u8>,
+
+    /// The number of nodes after which to stop searching.
+    pub nodes: Option<u64>,
+
+    /// The amount of time after which to stop searching.
+    pub movetime: Option<Duration>,
+}
+
+/// The outcome of a [`Search`], taken from the deepest iteration that was
+/// searched to completion.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SearchResult {
+    /// The best move found, or [`None`] if the game is already over.
+    pub best_move: Option<Move>,
+
+    /// The score of the position for the side to move. Mates are scored
+    /// relative to the root, as described by [`Value::mate_in`].
+    pub score: Value,
+
+    /// The depth of the last completed iteration.
+    pub depth: u8,
+
+    /// The deepest ply reached during the last completed iteration.
+    pub seldepth: u8,
+
+    /// The total number of nodes searched across every iteration.
+    pub nodes: u64,
+
+    /// The principal variation: the sequence of moves that both sides are
+    /// expected to play, starting with the best move.
+    pub pv: Vec<Move>,
+}
+
+/// A negamax principal variation search over a [`Position`], deepened one
+/// ply at a time until the [`Limits`] given to it have been reached.
+///
+/// Each iteration after the first few is searched with an aspiration window
+/// around the previous iteration's score, which is widened whenever the score
+/// falls outside of it. Once the depth runs out, a quiescence search resolves
+/// any pending captures before the position is evaluated, so that the score
+/// isn't thrown off by an exchange cut short at the horizon.
+///
+/// Every node searched is recorded in a [`TranspositionTable`], which may be
+/// shared with searches running on other threads.
+#[derive(Debug)]
+#[must_use]
+pub struct Search<'tt> {
+    tt:       &'tt TranspositionTable,
+    limits:   Limits,
+    start:    Instant,
+    nodes:    u64,
+    seldepth: u8,
+    stopped:  bool,
+
+    // the principal variation found at each ply of the current iteration,
+    // along with the one from the previous iteration to order moves by
+    pv:          Vec<Vec<Move>>,
+    previous_pv: Vec<Move>,
+}
+
+impl<'tt> Search<'tt> {
+    /// The depth from which iterations are searched with an aspiration
+    /// window.
+    const ASPIRATION_DEPTH: u8 = 4;
+
+    /// The initial distance of the aspiration window on either side of the
+    /// previous score.
+    const ASPIRATION_DELTA: Value = Value::new(32);
+
+    /// Creates a new search that will be bounded by the given `limits`,
+    /// recording what it finds in the transposition table `tt`.
+    ///
+    /// The search never advances the table's generation itself, since the
+    /// table may be shared with searches running on other threads. Whoever
+    /// owns the table should call [`TranspositionTable::new_search`] once
+    /// before every search, ahead of starting any of its threads.
+    pub fn new(limits: Limits, tt: &'tt TranspositionTable) -> Self {
+        Self {
+            tt,
+            limits,
+            start:       Instant::now(),
+            nodes:       0,
+            seldepth:    0,
+            stopped:     false,
+            pv:          vec![Vec::new(); usize::from(MAX_PLY) + 1],
+            previous_pv: Vec::new(),
+        }
+    }
+
+    /// Searches the `position` with iterative deepening, returning the
+    /// result of the deepest iteration completed before reaching the limits.
+    ///
+    /// The position is restored to its original state once the search is
+    /// over.
+    pub fn run(&mut self, position: &mut Position) -> SearchResult {
+        self.start   = Instant::now();
+        self.nodes   = 0;
+        self.stopped = false;
+        self.previous_pv.clear();
+
+        let max_depth = self.limits.depth.unwrap_or(MAX_PLY - 1).clamp(1, MAX_PLY - 1);
+
+        let mut result = SearchResult {
+            best_move: None,
+            score:     Value::DRAW,
+            depth:     0,
+            seldepth:  0,
+            nodes:     0,
+            pv:        Vec::new(),
+        };
+
+        for depth in 1..=max_depth {
+            self.seldepth = 0;
+
+            let score = self.aspiration(position, depth, result.score);
+
+            // an interrupted iteration can't be trusted, so the result of the
+            // previous one is kept instead
+            if self.stopped {
+                break;
+            }
+
+            self.previous_pv.clone_from(&self.pv[0]);
+
+            result.best_move = self.pv[0].first().copied();
+            result.score     = score;
+            result.depth     = depth;
+            result.seldepth  = self.seldepth;
+            result.pv.clone_from(&self.pv[0]);
+
+            // every mate within the current depth has been seen, so a deeper
+            // search can't find a shorter one
+            if score.is_mate() && i32::from(Value::MATE) - i32::from(score).abs() <= i32::from(depth) {
+                break;
+            }
+
+            if self.limits.movetime.map_or(false, |movetime| self.start.elapsed() >= movetime) {
+                break;
+            }
+        }
+
+        result.nodes = self.nodes;
+        result
+    }
+
+    /// Searches the root to the given `depth` with a window centered on the
+    /// `previous` iteration's score, widening it on whichever side the score
+    /// falls outside of until it lands within.
+    // the window is only ever split or grown by half, where rounding makes no
+    // difference worth caring about
+    #[allow(clippy::integer_division)]
+    fn aspiration(&mut self, position: &mut Position, depth: u8, previous: Value) -> Value {
+        let mut delta = Self::ASPIRATION_DELTA;
+
+        let (mut alpha, mut beta) = if depth >= Self::ASPIRATION_DEPTH && !previous.is_mate() {
+            (
+                (previous - delta).max(-Value::INFINITE),
+                (previous + delta).min( Value::INFINITE),
+            )
+        } else {
+            (-Value::INFINITE, Value::INFINITE)
+        };
+
+        loop {
+            let score = self.search(position, alpha, beta, depth, 0, true);
+
+            if self.stopped {
+                return score;
+            }
+
+            if score <= alpha {
+                // the window is pulled down along with the score, so the
+                // next search doesn't just fail high instead
+                beta  = Value::new((i32::from(alpha) + i32::from(beta)) / 2);
+                alpha = (score - delta).max(-Value::INFINITE);
+            } else if score >= beta {
+                beta = (score + delta).min(Value::INFINITE);
+            } else {
+                return score;
+            }
+
+            delta += Value::new(i32::from(delta) / 2);
+        }
+    }
+
+    /// Searches the position to the given `depth`, returning its score for
+    /// the side to move if it lies within `alpha..beta`, or a bound on the
+    /// score otherwise.
+    ///
+    /// Only the first move at each node is searched with the full window.
+    /// Every other move is searched with a null window to prove it's no
+    /// better, and only re-searched with the full window at PV nodes when it
+    /// turns out to be.
+    fn search(
+        &mut self,
+        position: &mut Position,
+        mut alpha: Value,
+        mut beta:  Value,
+        depth:     u8,
+        ply:       u8,
+        pv_node:   bool,
+    ) -> Value {
+        if depth == 0 {
+            return self.qsearch(position, alpha, beta, ply, pv_node);
+        }
+
+        let index = usize::from(ply);
+
+        if let Some(value) = self.enter(position, ply) {
+            return value;
+        }
+
+        if ply > 0 {
+            if position.is_draw(u16::from(ply)) || position.has_insufficient_material() {
+                return Value::DRAW;
+            }
+
+            // even mating on the very next move can't beat a shorter mate
+            // that's already been found, and being mated right now can't be
+            // any worse than a mate already found against us
+            alpha = alpha.max(Value::mated_in(ply));
+            beta  = beta .min(Value::mate_in(ply + 1));
+
+            if alpha >= beta {
+                return alpha;
+            }
+        }
+
+        let entry = self.probe(position);
+
+        let cutoff = entry
+            .filter(|entry| !pv_node && entry.depth >= depth)
+            .and_then(|entry| Self::tt_cutoff(entry, alpha, beta, ply));
+
+        if let Some(value) = cutoff {
+            return value;
+        }
+
+        let mut moves = position.legal_moves();
+
+        if moves.is_empty() {
+            return Self::no_moves_value(position, ply);
+        }
+
+        self.order_moves(position, &mut moves, index, entry.and_then(|entry| entry.mv));
+
+        let original_alpha = alpha;
+
+        let mut best      = -Value::INFINITE;
+        let mut best_move = None;
+
+        for (i, &mv) in moves.iter().enumerate() {
+            position.do_move(mv);
+            self.tt.prefetch(position.key());
+
+            let score = if i == 0 {
+                -self.search(position, -beta, -alpha, depth - 1, ply + 1, pv_node)
+            } else {
+                let null_window = alpha + Value::new(1);
+                let score       = -self.search(position, -null_window, -alpha, depth - 1, ply + 1, false);
+
+                if pv_node && score > alpha && score < beta {
+                    -self.search(position, -beta, -alpha, depth - 1, ply + 1, true)
+                } else {
+                    score
+                }
+            };
+
+            position.undo_move(mv);
+
+            if self.stopped {
+                return Value::ZERO;
+            }
+
+            if score <= best {
+                continue;
+            }
+
+            best = score;
+
+            if score > alpha {
+                best_move = Some(mv);
+
+                if pv_node {
+                    self.update_pv(index, mv);
+                }
+
+                if score >= beta {
+                    break;
+                }
+
+                alpha = score;
+            }
+        }
+
+        let eval = entry.map_or_else(|| Self::evaluate(position), |entry| entry.eval);
+
+        self.store(position, best_move, best, eval, depth, ply, original_alpha, beta);
+
+        best
+    }
+
+    /// Searches only the captures and queen promotions in the position (or
+    /// every evasion, when in check) until it becomes quiet, returning its
+    /// score for the side to move if it lies within `alpha..beta`, or a bound
+    /// on the score otherwise.
+    ///
+    /// Unless in check, the side to move may "stand pat" and decline to
+    /// capture anything, so the static evaluation is a lower bound on the
+    /// score. Captures that lose material by static exchange evaluation are
+    /// skipped, as are those which couldn't raise the evaluation to alpha
+    /// even if the captured piece were won for free.
+    fn qsearch(
+        &mut self,
+        position: &mut Position,
+        mut alpha: Value,
+        beta:      Value,
+        ply:       u8,
+        pv_node:   bool,
+    ) -> Value {
+        let index = usize::from(ply);
+
+        if let Some(value) = self.enter(position, ply) {
+            return value;
+        }
+
+        if position.is_draw(u16::from(ply)) || position.has_insufficient_material() {
+            return Value::DRAW;
+        }
+
+        let entry = self.probe(position);
+
+        let cutoff = entry
+            .filter(|_| !pv_node)
+            .and_then(|entry| Self::tt_cutoff(entry, alpha, beta, ply));
+
+        if let Some(value) = cutoff {
+            return value;
+        }
+
+        let in_check = position.checkers().is_any();
+
+        // material is given away or blown up rather than simply won in these
+        // variants, so the exchanges can't be judged by the pieces alone
+        let prunable = !in_check
+            && position.ruleset() != Ruleset::Antichess
+            && position.ruleset() != Ruleset::Atomic;
+
+        let mut moves = if in_check {
+            let moves = position.legal_moves();
+
+            if moves.is_empty() {
+                return Self::no_moves_value(position, ply);
+            }
+
+            moves
+        } else {
+            let mut moves = position.generate(GenType::Captures);
+
+            moves.retain(|mv| {
+                (mv.move_type() != MoveType::Promotion || mv.promotion() == Token::Queen)
+                    && position.is_legal_unchecked(mv)
+            });
+
+            moves
+        };
+
+        let stand_pat = entry.map_or_else(|| Self::evaluate(position), |entry| entry.eval);
+
+        let mut best      = if in_check { -Value::INFINITE } else { stand_pat };
+        let mut best_move = None;
+
+        if best >= beta {
+            self.store(position, None, best, stand_pat, 0, ply, alpha, beta);
+            return best;
+        }
+
+        let original_alpha = alpha;
+
+        alpha = alpha.max(best);
+
+        self.order_moves(position, &mut moves, index, entry.and_then(|entry| entry.mv));
+
+        for &mv in &moves {
+            if prunable {
+                if !position.see_ge(mv, Value::ZERO) {
+                    continue;
+                }
+
+                // even winning the captured piece outright wouldn't be enough
+                let futility = stand_pat + Self::material_gain(position, mv) + DELTA_MARGIN;
+
+                if futility <= alpha
+                    && mv.move_type() != MoveType::Promotion
+                    && !position.gives_check(mv)
+                {
+                    best = best.max(futility);
+                    continue;
+                }
+            }
+
+            position.do_move(mv);
+            self.tt.prefetch(position.key());
+            let score = -self.qsearch(position, -beta, -alpha, ply + 1, pv_node);
+            position.undo_move(mv);
+
+            if self.stopped {
+                return Value::ZERO;
+            }
+
+            if score <= best {
+                continue;
+            }
+
+            best = score;
+
+            if score > alpha {
+                best_move = Some(mv);
+
+                if pv_node {
+                    self.update_pv(index, mv);
+                }
+
+                if score >= beta {
+                    break;
+                }
+
+                alpha = score;
+            }
+        }
+
+        self.store(position, best_move, best, stand_pat, 0, ply, original_alpha, beta);
+
+        best
+    }
+
+    /// Does the bookkeeping common to every node of the search, returning
+    /// the node's value straight away if there's no need to search it: if
+    /// the search has been stopped, if the game is already over, or if the
+    /// maximum ply has been reached.
+    fn enter(&mut self, position: &Position, ply: u8) -> Option<Value> {
+        self.pv[usize::from(ply)].clear();
+
+        self.nodes   += 1;
+        self.seldepth = self.seldepth.max(ply);
+
+        self.check_limits();
+
+        if self.stopped {
+            return Some(Value::ZERO);
+        }
+
+        if let Some(outcome) = position.variant_outcome() {
+            return Some(Self::outcome_value(outcome, position.turn(), ply));
+        }
+
+        if ply >= MAX_PLY {
+            return Some(Self::evaluate(position));
+        }
+
+        None
+    }
+
+    /// Looks up the position in the transposition table, ignoring any entry
+    /// whose move isn't legal here: it must have been written for another
+    /// position whose key happens to collide, or torn by a write from
+    /// another thread.
+    fn probe(&self, position: &Position) -> Option<TtEntry> {
+        self.tt.probe(position.key())
+            .filter(|entry| entry.mv.map_or(true, |mv| position.is_legal(mv)))
+    }
+
+    /// Returns the value stored in a transposition table `entry` if it's
+    /// enough to settle the node without searching it, given its window.
+    fn tt_cutoff(entry: TtEntry, alpha: Value, beta: Value, ply: u8) -> Option<Value> {
+        let value = entry.value.from_tt(ply);
+
+        let cutoff = match entry.bound {
+            Bound::Exact => true,
+            Bound::Lower => value >= beta,
+            Bound::Upper => value <= alpha,
+        };
+
+        cutoff.then_some(value)
+    }
+
+    /// Records the result of searching a node in the transposition table,
+    /// along with which side of the true value it lies on given the window
+    /// it was searched with. Nothing is recorded once the search has been
+    /// stopped, since the result can't be trusted.
+    #[allow(clippy::too_many_arguments)]
+    fn store(
+        &self,
+        position:  &Position,
+        best_move: Option<Move>,
+        best:      Value,
+        eval:      Value,
+        depth:     u8,
+        ply:       u8,
+        alpha:     Value,
+        beta:      Value,
+    ) {
+        if self.stopped {
+            return;
+        }
+
+        let bound = if best >= beta {
+            Bound::Lower
+        } else if best > alpha {
+            Bound::Exact
+        } else {
+            Bound::Upper
+        };
+
+        self.tt.store(position.key(), best_move, best.to_tt(ply), eval, depth, bound);
+    }
+
+    /// Orders `moves` so the most promising are searched first: the move
+    /// stored in the transposition table, the move played at this ply in the
+    /// previous iteration's principal variation, then captures and
+    /// promotions by the material they win, and then the rest.
+    fn order_moves(&self, position: &Position, moves: &mut MoveList, ply: usize, tt_move: Option<Move>) {
+        let pv_move = self.previous_pv.get(ply).copied();
+
+        moves.sort_unstable_by_key(|&mv| {
+            if Some(mv) == tt_move {
+                return i32::MIN;
+            }
+
+            if Some(mv) == pv_move {
+                return i32::MIN + 1;
+            }
+
+            -i32::from(Self::material_gain(position, mv))
+        });
+    }
+
+    /// Returns the material won by `mv` before any recapture: the value of
+    /// the captured piece, plus the value gained by promoting a pawn.
+    fn material_gain(position: &Position, mv: Move) -> Value {
+        let captured = match mv.move_type() {
+            MoveType::EnPassant          => Some(Token::Pawn),
+            _ if position.is_capture(mv) => position[mv.destination()].map(Piece::token),
+            _                            => None,
+        };
+
+        // in crazyhouse the captured piece also goes into the capturer's
+        // pocket, reverting to a pawn if it had been promoted
+        let pocketed = match captured {
+            Some(_) if position.ruleset() != Ruleset::Crazyhouse      => Value::ZERO,
+            Some(_) if position.promoted().contains(mv.destination()) => Value::PAWN,
+            Some(token)                                               => Value::of(token),
+            None                                                      => Value::ZERO,
+        };
+
+        let promotion = match mv.move_type() {
+            MoveType::Promotion => Value::of(mv.promotion()) - Value::PAWN,
+            _                   => Value::ZERO,
+        };
+
+        captured.map_or(Value::ZERO, Value::of) + pocketed + promotion
+    }
+
+    /// Makes `mv` followed by the principal variation found below it the
+    /// principal variation at `ply`.
+    fn update_pv(&mut self, ply: usize, mv: Move) {
+        let (head, tail) = self.pv.split_at_mut(ply + 1);
+        let line         = &mut head[ply];
+
+        line.clear();
+        line.push(mv);
+        line.extend_from_slice(&tail[0]);
+    }
+
+    /// Stops the search once the node count or the time allotted has run
+    /// out. The clock is only consulted every so often, since reading it
+    /// isn't free.
+    fn check_limits(&mut self) {
+        // the first iteration is always completed, so there's a move to play
+        if self.previous_pv.is_empty() {
+            return;
+        }
+
+        if self.limits.nodes.map_or(false, |nodes| self.nodes >= nodes) {
+            self.stopped = true;
+        }
+
+        if self.nodes % CLOCK_INTERVAL == 0
+            && self.limits.movetime.map_or(false, |movetime| self.start.elapsed() >= movetime)
+        {
+            self.stopped = true;
+        }
+    }
+
+    /// Scores a position where the side to move has no legal moves: a loss
+    /// when checkmated, a win for the stalemated side in antichess, and
+    /// otherwise a draw by stalemate.
+    fn no_moves_value(position: &Position, ply: u8) -> Value {
+        if position.ruleset() == Ruleset::Antichess {
+            Value::mate_in(ply)
+        } else if position.checkers().is_any() {
+            Value::mated_in(ply)
+        } else {
+            Value::DRAW
+        }
+    }
+
+    /// Scores a finished game from the perspective of `turn`, with wins
+    /// scored as mates at the given `ply`.
+    fn outcome_value(outcome: GameOutcome, turn: Color, ply: u8) -> Value {
+        match outcome.winner() {
+            Some(winner) if winner == turn => Value::mate_in(ply),
+            Some(_)                        => Value::mated_in(ply),
+            None                           => Value::DRAW,
+        }
+    }
+
+    /// Statically evaluates the position for the side to move by counting
+    /// the material each side has on the board and in hand.
+    #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+    fn evaluate(position: &Position) -> Value {
+        let us = position.turn();
+
+        Piece::iter().fold(Value::ZERO, |total, piece| {
+            let count = position.bitboard_for_token(piece).count() + usize::from(position.pocket(piece));
+            let value = Value::new(i32::from(Value::of(piece.token())) * count as i32);
+
+            if piece.color() == us { total + value } else { total - value }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn search(ruleset: Ruleset, fen: &str, depth: u8) -> SearchResult {
+        let mut position = Position::from_fen(ruleset, fen.as_bytes());
+        let tt           = TranspositionTable::new(1);
+
+        Search::new(Limits { depth: Some(depth), ..Limits::default() }, &tt).run(&mut position)
+    }
+
+    /// Runs a quiescence search with the given window, returning its value
+    /// along with the number of nodes it took.
+    fn qsearch(fen: &str, alpha: Value, beta: Value) -> (Value, u64) {
+        let mut position = Position::from_fen(Ruleset::Standard, fen.as_bytes());
+        let tt           = TranspositionTable::new(1);
+        let mut search   = Search::new(Limits::default(), &tt);
+        let value        = search.qsearch(&mut position, alpha, beta, 0, true);
+
+        (value, search.nodes)
+    }
+
+    #[test]
+    fn search_mate_in_one() {
+        let result = search(Ruleset::Standard, "6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1", 3);
+
+        assert_eq!(Some(Move::new(Square::A1, Square::A8)), result.best_move);
+        assert_eq!(Value::mate_in(1), result.score);
+        assert_eq!(vec![Move::new(Square::A1, Square::A8)], result.pv);
+    }
+
+    #[test]
+    fn search_mate_in_two() {
+        let result = search(Ruleset::Standard, "k7/8/2K5/8/8/8/8/7R w - - 0 1", 6);
+
+        assert_eq!(Value::mate_in(3), result.score);
+        assert_eq!(Some(2), result.score.mate_moves());
+        assert_eq!(3, result.pv.len());
+
+        // the search stops as soon as it's seen every mate that short
+        assert_eq!(3, result.depth);
+    }
+
+    #[test]
+    fn search_mated() {
+        let result = search(Ruleset::Standard, "k7/8/1K6/8/8/8/8/7R b - - 1 1", 4);
+
+        assert_eq!(Value::mated_in(2), result.score);
+        assert_eq!(Some(Move::new(Square::A8, Square::B8)), result.best_move);
+    }
+
+    #[test]
+    fn search_wins_material() {
+        let result = search(Ruleset::Standard, "4k3/8/8/3q4/8/8/8/3RK3 w - - 0 1", 3);
+
+        assert_eq!(Some(Move::new(Square::D1, Square::D5)), result.best_move);
+        assert!(result.score > Value::ZERO);
+    }
+
+    #[test]
+    fn search_horizon() {
+        // the pawn on d5 looks free at the horizon, but it's defended
+        let fen      = "4k3/8/2p5/3p4/8/8/8/3QK3 w - - 0 1";
+        let position = Position::from_fen(Ruleset::Standard, fen.as_bytes());
+        let result   = search(Ruleset::Standard, fen, 1);
+
+        assert_ne!(Some(Move::new(Square::D1, Square::D5)), result.best_move);
+        assert_eq!(Search::evaluate(&position), result.score);
+    }
+
+    #[test]
+    fn qsearch_captures() {
+        let (value, _) = qsearch("4k3/8/8/3q4/8/8/8/3RK3 w - - 0 1", -Value::INFINITE, Value::INFINITE);
+
+        assert_eq!(Value::ROOK, value);
+    }
+
+    #[test]
+    fn qsearch_stands_pat() {
+        let fen      = "4k3/8/2p5/3p4/8/8/8/3QK3 w - - 0 1";
+        let position = Position::from_fen(Ruleset::Standard, fen.as_bytes());
+
+        // taking the defended pawn loses the queen by static exchange, so
+        // isn't even searched
+        assert_eq!((Search::evaluate(&position), 1),
+            qsearch(fen, -Value::INFINITE, Value::INFINITE));
+    }
+
+    #[test]
+    fn qsearch_delta_pruning() {
+        let fen      = "4k3/8/8/3p4/8/8/8/3QK3 w - - 0 1";
+        let position = Position::from_fen(Ruleset::Standard, fen.as_bytes());
+        let alpha    = Search::evaluate(&position) + Value::ROOK;
+
+        // winning a pawn can't possibly make up for a rook
+        let (value, nodes) = qsearch(fen, alpha, Value::INFINITE);
+
+        assert!(value <= alpha);
+        assert_eq!(1, nodes);
+    }
+
+    #[test]
+    fn qsearch_delta_pruning_crazyhouse() {
+        let fen          = "4k3/8/8/3n4/8/8/8/3QK3[] w - - 0 1";
+        let mut position = Position::from_fen(Ruleset::Crazyhouse, fen.as_bytes());
+        let tt           = TranspositionTable::new(1);
+        let mut search   = Search::new(Limits::default(), &tt);
+        let eval         = Search::evaluate(&position);
+        let alpha        = eval + Value::KNIGHT + DELTA_MARGIN;
+
+        // the knight lands in white's pocket as well as leaving the board,
+        // which is worth more than the knight alone
+        let value = search.qsearch(&mut position, alpha, Value::INFINITE, 0, true);
+
+        assert_eq!(eval + Value::KNIGHT + Value::KNIGHT, value);
+        assert!(search.nodes > 1);
+    }
+
+    #[test]
+    fn qsearch_stores_in_table() {
+        let fen          = "4k3/8/8/3q4/8/8/8/3RK3 w - - 0 1";
+        let mut position = Position::from_fen(Ruleset::Standard, fen.as_bytes());
+        let tt           = TranspositionTable::new(1);
+        let mut search   = Search::new(Limits::default(), &tt);
+        let value        = search.qsearch(&mut position, -Value::INFINITE, Value::INFINITE, 0, true);
+        let entry        = tt.probe(position.key()).unwrap();
+
+        assert_eq!(Some(Move::new(Square::D1, Square::D5)), entry.mv);
+        assert_eq!(value,        entry.value);
+        assert_eq!(0,            entry.depth);
+        assert_eq!(Bound::Exact, entry.bound);
+    }
+
+    #[test]
+    fn qsearch_in_check() {
+        // there's no standing pat while in check, so being mated is found
+        let (value, _) = qsearch("R5k1/5ppp/8/8/8/8/8/6K1 b - - 1 1", -Value::INFINITE, Value::INFINITE);
+
+        assert_eq!(Value::mated_in(0), value);
+
+        // and a check is evaded even when nothing can be captured
+        let fen      = "k7/8/8/8/8/8/8/R3K3 b - - 0 1";
+        let position = Position::from_fen(Ruleset::Standard, fen.as_bytes());
+
+        assert_eq!(Search::evaluate(&position), qsearch(fen, -Value::INFINITE, Value::INFINITE).0);
+    }
+
+    #[test]
+    fn search_pv_is_legal() {
+        let fen          = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+        let mut position = Position::from_fen(Ruleset::Standard, fen.as_bytes());
+        let result       = search(Ruleset::Standard, fen, 4);
+
+        assert_eq!(4, result.depth);
+        assert_eq!(result.best_move, result.pv.first().copied());
+        assert!(result.seldepth >= result.depth);
+
+        for &mv in &result.pv {
+            assert!(position.is_legal(mv), "{mv:?}");
+            position.do_move(mv);
+        }
+    }
+
+    #[test]
+    fn search_game_over() {
+        // stalemate
+        let result = search(Ruleset::Standard, "k7/8/1Q6/8/8/8/8/7K b - - 0 1", 4);
+
+        assert_eq!(None, result.best_move);
+        assert_eq!(Value::DRAW, result.score);
+        assert!(result.pv.is_empty());
+    }
+
+    #[test]
+    fn search_variant_win() {
+        // stepping into the center wins king of the hill on the spot
+        let result = search(Ruleset::KingOfTheHill, "8/8/8/8/8/2K5/8/k7 w - - 0 1", 4);
+
+        assert_eq!(Some(Move::new(Square::C3, Square::D4)), result.best_move);
+        assert_eq!(Value::mate_in(1), result.score);
+    }
+
+    #[test]
+    fn search_node_limit() {
+        let mut position = Position::from_fen(Ruleset::Standard,
+            b"rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+
+        let tt     = TranspositionTable::new(1);
+        let result = Search::new(Limits { nodes: Some(1), ..Limits::default() }, &tt).run(&mut position);
+
+        // the first iteration is always completed
+        assert_eq!(1, result.depth);
+        assert!(result.best_move.is_some());
+
+        // and the position is left untouched
+        assert_eq!(Position::from_fen(Ruleset::Standard,
+            b"rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"), position);
+    }
+
+    #[test]
+    fn search_reuses_table() {
+        let fen    = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+        let limits = Limits { depth: Some(4), ..Limits::default() };
+        let tt     = TranspositionTable::new(1);
+
+        let mut position = Position::from_fen(Ruleset::Standard, fen.as_bytes());
+
+        tt.new_search();
+        let first = Search::new(limits, &tt).run(&mut position);
+
+        tt.new_search();
+        let second = Search::new(limits, &tt).run(&mut position);
+
+        // the second search is mostly answered by what the first one found
+        assert_eq!(first.score, second.score);
+        assert!(second.nodes < first.nodes);
+        assert!(tt.hashfull() > 0);
+    }
+
+    #[test]
+    fn probe_ignores_foreign_move() {
+        let position = Position::from_fen(Ruleset::Standard, b"6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1");
+        let tt       = TranspositionTable::new(1);
+        let search   = Search::new(Limits::default(), &tt);
+
+        // an entry left by a colliding position, or torn by another thread,
+        // with a move that isn't even possible here
+        tt.store(position.key(), Some(Move::new(Square::E2, Square::E4)),
+            Value::DRAW, Value::DRAW, 10, Bound::Exact);
+
+        assert!(tt.probe(position.key()).is_some());
+        assert_eq!(None, search.probe(&position));
+
+        // whereas an entry with a legal move is trusted
+        tt.store(position.key(), Some(Move::new(Square::A1, Square::A8)),
+            Value::mate_in(1), Value::DRAW, 10, Bound::Exact);
+
+        assert_eq!(Some(Move::new(Square::A1, Square::A8)), search.probe(&position).and_then(|entry| entry.mv));
+    }
+
+    #[test]
+    fn search_shared_between_threads() {
+        let fen = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+        let tt  = TranspositionTable::new(1);
+
+        tt.new_search();
+
+        let results = std::thread::scope(|scope| {
+            let threads = (0..4).map(|_| scope.spawn(|| {
+                let mut position = Position::from_fen(Ruleset::Standard, fen.as_bytes());
+
+                Search::new(Limits { depth: Some(4), ..Limits::default() }, &tt).run(&mut position)
+            })).collect::<Vec<_>>();
+
+            threads.into_iter().map(|thread| thread.join().unwrap()).collect::<Vec<_>>()
+        });
+
+        for result in results {
+            let mut position = Position::from_fen(Ruleset::Standard, fen.as_bytes());
+
+            assert_eq!(4, result.depth);
+
+            for &mv in &result.pv {
+                assert!(position.is_legal(mv), "{mv:?}");
+                position.do_move(mv);
+            }
+        }
+    }
+}
@@ -0,0 +1,564 @@
+use crate::prelude::*;
+use stockfish_core::prelude::*;
+use stockfish_core::hash::Key;
+
+use core::mem::size_of;
+use core::num::NonZeroUsize;
+use core::sync::atomic::{AtomicU16, AtomicU64, AtomicU8, Ordering};
+
+/// The number of entries sharing a single cache line.
+const CLUSTER_SIZE: usize = 6;
+
+/// The number of clusters sampled by [`TranspositionTable::hashfull`].
+const HASHFULL_SAMPLE: usize = 1000;
+
+/// The generation occupies the upper six bits of each entry's generation and
+/// bound byte, leaving the lower two for the [`Bound`].
+const GENERATION_SHIFT: u8 = 2;
+const GENERATION_DELTA: u8 = 1 << GENERATION_SHIFT;
+const GENERATION_MASK:  u8 = !(GENERATION_DELTA - 1);
+
+/// The number of plies of depth that a single generation of age is worth
+/// when choosing an entry to replace.
+const AGE_WEIGHT: i32 = 8;
+
+/// Which side of the true value a score stored in a transposition table is
+/// known to lie on.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum Bound {
+    /// The search failed low, so the true value is at most the score.
+    Upper = 1,
+
+    /// The search failed high, so the true value is at least the score.
+    Lower = 2,
+
+    /// The score is the true value.
+    Exact = 3,
+}
+
+/// A snapshot of an entry read from a [`TranspositionTable`].
+///
+/// Entries may be overwritten by other threads at any time, possibly
+/// partway through being read, so the move must be checked for legality with
+/// [`Position::is_legal`] before it's played.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct TtEntry {
+    /// The best move found, if any.
+    pub mv: Option<Move>,
+
+    /// The score found, with mates counted from the position as described by
+    /// [`Value::to_tt`].
+    pub value: Value,
+
+    /// The static evaluation of the position.
+    pub eval: Value,
+
+    /// The depth the position was searched to.
+    pub depth: u8,
+
+    /// Which side of the true value `value` lies on.
+    pub bound: Bound,
+}
+
+/// A hash table of previously searched positions, shared between any number
+/// of search threads without locking.
+///
+/// Entries are grouped into clusters the size of a cache line, so that every
+/// entry a position might occupy can be examined with a single memory access.
+/// Each entry is made up of a 16-bit fragment of the position's key and a
+/// 64-bit word packing the rest of its contents, each of which is written
+/// atomically. Writes from different threads may still interleave between the
+/// two, so a position may be matched with another position's data; this is
+/// rare enough to be tolerated, as long as moves are validated before use.
+#[must_use]
+pub struct TranspositionTable {
+    clusters:   Vec<Cluster>,
+    generation: AtomicU8,
+}
+
+#[derive(Debug, Default)]
+#[repr(C, align(64))]
+struct Cluster {
+    keys: [AtomicU16; CLUSTER_SIZE],
+    data: [AtomicU64; CLUSTER_SIZE],
+}
+
+/// The contents of an entry besides its key fragment, packed into a single
+/// word so that they can be read and written together.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+struct Data {
+    mv:        u16,
+    value:     i16,
+    eval:      i16,
+    depth:     u8,
+    gen_bound: u8,
+}
+
+impl TranspositionTable {
+    /// Creates a table occupying roughly `mb` megabytes.
+    pub fn new(mb: usize) -> Self {
+        let mut table = Self {
+            clusters:   Vec::new(),
+            generation: AtomicU8::new(0),
+        };
+
+        table.resize(mb);
+        table
+    }
+
+    /// Replaces the table with an empty one occupying roughly `mb`
+    /// megabytes. The table always has room for at least one cluster.
+    ///
+    /// Like [`TranspositionTable::clear`], the new table is zeroed by as many
+    /// threads as there are processors available.
+    // a partial cluster at the end wouldn't fit in the table anyway
+    #[allow(clippy::integer_division)]
+    pub fn resize(&mut self, mb: usize) {
+        let count = (mb.saturating_mul(1024 * 1024) / size_of::<Cluster>()).max(1);
+
+        // the old table is freed first, so that both never have to fit in
+        // memory at once
+        self.clusters = Vec::new();
+
+        let mut clusters = Vec::with_capacity(count);
+
+        Self::for_each_parallel(&mut clusters.spare_capacity_mut()[..count], |cluster| {
+            let _ = cluster.write(Cluster::default());
+        });
+
+        #[allow(unsafe_code)]
+        // SAFETY: the first `count` clusters have all just been initialized
+        unsafe {
+            clusters.set_len(count);
+        }
+
+        self.clusters   = clusters;
+        self.generation = AtomicU8::new(0);
+    }
+
+    /// Empties every entry in the table, splitting the work across as many
+    /// threads as there are processors available.
+    pub fn clear(&mut self) {
+        Self::for_each_parallel(&mut self.clusters, |cluster| *cluster = Cluster::default());
+
+        self.generation = AtomicU8::new(0);
+    }
+
+    /// Advances the table's generation, which should be done at the start of
+    /// every new search. Entries from older generations are more readily
+    /// replaced.
+    #[inline]
+    pub fn new_search(&self) {
+        let _ = self.generation.fetch_add(GENERATION_DELTA, Ordering::Relaxed);
+    }
+
+    /// Looks up the entry for the position with the given `key`, if there is
+    /// one.
+    ///
+    /// Finding an entry refreshes its generation, so positions that are
+    /// still being reached aren't replaced as though they were stale.
+    pub fn probe(&self, key: Key) -> Option<TtEntry> {
+        let cluster    = self.cluster(key);
+        let fragment   = Self::fragment(key);
+        let generation = self.generation.load(Ordering::Relaxed);
+
+        for (stored, data) in cluster.keys.iter().zip(&cluster.data) {
+            if stored.load(Ordering::Relaxed) != fragment {
+                continue;
+            }
+
+            let word  = data.load(Ordering::Relaxed);
+            let entry = Data::unpack(word);
+
+            if entry.depth == 0 {
+                return None;
+            }
+
+            if entry.gen_bound & GENERATION_MASK != generation {
+                let refreshed = Data { gen_bound: generation | (entry.gen_bound & !GENERATION_MASK), ..entry };
+
+                // a racing write wins, since its contents are newer anyway
+                let _ = data.compare_exchange(word, refreshed.pack(), Ordering::Relaxed, Ordering::Relaxed);
+            }
+
+            return entry.into_entry();
+        }
+
+        None
+    }
+
+    /// Stores the result of searching the position with the given `key`.
+    ///
+    /// An existing entry for the same position is updated in place, keeping
+    /// its move if no new one is given, unless it holds a deeper search than
+    /// this one. Otherwise the entry replaced is the one with the least depth,
+    /// counting older generations as shallower.
+    pub fn store(&self, key: Key, mv: Option<Move>, value: Value, eval: Value, depth: u8, bound: Bound) {
+        let cluster    = self.cluster(key);
+        let fragment   = Self::fragment(key);
+        let generation = self.generation.load(Ordering::Relaxed);
+
+        let mut replace = 0;
+        let mut worth   = i32::MAX;
+
+        for i in 0..CLUSTER_SIZE {
+            let entry = Data::unpack(cluster.data[i].load(Ordering::Relaxed));
+
+            if cluster.keys[i].load(Ordering::Relaxed) == fragment || entry.depth == 0 {
+                replace = i;
+                break;
+            }
+
+            let age = i32::from(generation.wrapping_sub(entry.gen_bound & GENERATION_MASK) >> GENERATION_SHIFT);
+
+            let entry_worth = i32::from(entry.depth) - AGE_WEIGHT * age;
+
+            if entry_worth < worth {
+                replace = i;
+                worth   = entry_worth;
+            }
+        }
+
+        let word = cluster.data[replace].load(Ordering::Relaxed);
+        let old  = Data::unpack(word);
+        let same = cluster.keys[replace].load(Ordering::Relaxed) == fragment && old.depth != 0;
+
+        let mv = match mv {
+            Some(mv)     => mv.as_u16(),
+            None if same => old.mv,
+            None         => 0,
+        };
+
+        // a deeper result for the same position is more useful than this one,
+        // unless this one is exact, though this one's move is more recent
+        if same && bound != Bound::Exact && u16::from(depth) + 1 + 4 <= u16::from(old.depth) {
+            let updated = Data { mv, ..old };
+
+            // a racing write wins, since its contents are newer anyway
+            let _ = cluster.data[replace].compare_exchange(word, updated.pack(), Ordering::Relaxed, Ordering::Relaxed);
+
+            return;
+        }
+
+        let data = Data {
+            mv,
+            value:     Self::narrow(value),
+            eval:      Self::narrow(eval),
+            depth:     depth.saturating_add(1),
+            gen_bound: generation | bound as u8,
+        };
+
+        cluster.data[replace].store(data.pack(), Ordering::Relaxed);
+        cluster.keys[replace].store(fragment,    Ordering::Relaxed);
+    }
+
+    /// Returns an estimate of how full the table is, in permille, counting
+    /// only entries written during the current generation.
+    #[must_use]
+    // the estimate is rounded down to a whole permille, as UCI expects
+    #[allow(clippy::integer_division)]
+    pub fn hashfull(&self) -> usize {
+        let generation = self.generation.load(Ordering::Relaxed);
+        let sample     = &self.clusters[..self.clusters.len().min(HASHFULL_SAMPLE)];
+
+        let used = sample.iter()
+            .flat_map(|cluster| &cluster.data)
+            .map(|data| Data::unpack(data.load(Ordering::Relaxed)))
+            .filter(|entry| entry.depth != 0 && entry.gen_bound & GENERATION_MASK == generation)
+            .count();
+
+        used * 1000 / (sample.len() * CLUSTER_SIZE)
+    }
+
+    /// Hints to the processor that the cluster for `key` is about to be
+    /// accessed, so that it can be loaded into the cache in the meantime.
+    #[inline]
+    pub fn prefetch(&self, key: Key) {
+        #[cfg(target_arch = "x86_64")]
+        {
+            use core::arch::x86_64::{_mm_prefetch, _MM_HINT_T0};
+
+            let pointer = core::ptr::addr_of!(*self.cluster(key)).cast::<i8>();
+
+            #[allow(unsafe_code)]
+            // SAFETY: prefetching is only a hint and never faults, and SSE is
+            // always available on x86_64
+            unsafe {
+                _mm_prefetch::<_MM_HINT_T0>(pointer);
+            }
+        }
+
+        #[cfg(not(target_arch = "x86_64"))]
+        let _ = key;
+    }
+
+    /// Applies `f` to every one of the `items`, splitting the work across as
+    /// many threads as there are processors available.
+    fn for_each_parallel<T: Send>(items: &mut [T], f: impl Fn(&mut T) + Sync) {
+        let threads = std::thread::available_parallelism().map_or(1, NonZeroUsize::get);
+        let chunk   = items.len().div_ceil(threads).max(1);
+
+        std::thread::scope(|scope| {
+            for items in items.chunks_mut(chunk) {
+                let task = &f;
+                let _ = scope.spawn(move || items.iter_mut().for_each(task));
+            }
+        });
+    }
+
+    /// Returns the cluster the position with the given `key` belongs in,
+    /// chosen by the upper bits of the key.
+    #[inline]
+    fn cluster(&self, key: Key) -> &Cluster {
+        let len   = self.clusters.len() as u128;
+        let index = (u128::from(u64::from(key)) * len) >> 64;
+
+        #[allow(clippy::cast_possible_truncation)]
+        &self.clusters[index as usize]
+    }
+
+    /// Returns the lower bits of the `key` that identify a position within
+    /// its cluster.
+    #[inline]
+    #[allow(clippy::cast_possible_truncation)]
+    fn fragment(key: Key) -> u16 {
+        u64::from(key) as u16
+    }
+
+    /// Converts a value to fit in an entry. Every score produced by a search
+    /// lies well within range.
+    #[allow(clippy::cast_possible_truncation)]
+    fn narrow(value: Value) -> i16 {
+        i32::from(value).clamp(i16::MIN.into(), i16::MAX.into()) as i16
+    }
+}
+
+impl core::fmt::Debug for TranspositionTable {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("TranspositionTable")
+            .field("clusters",   &self.clusters.len())
+            .field("generation", &self.generation)
+            .finish()
+    }
+}
+
+impl Data {
+    #[allow(clippy::cast_sign_loss)]
+    const fn pack(self) -> u64 {
+        (self.mv as u64)
+            | ((self.value as u16 as u64) << 16)
+            | ((self.eval  as u16 as u64) << 32)
+            | ((self.depth as u64)        << 48)
+            | ((self.gen_bound as u64)    << 56)
+    }
+
+    #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+    const fn unpack(word: u64) -> Self {
+        Self {
+            mv:        word as u16,
+            value:     (word >> 16) as u16 as i16,
+            eval:      (word >> 32) as u16 as i16,
+            depth:     (word >> 48) as u8,
+            gen_bound: (word >> 56) as u8,
+        }
+    }
+
+    fn into_entry(self) -> Option<TtEntry> {
+        let bound = match self.gen_bound & !GENERATION_MASK {
+            1 => Bound::Upper,
+            2 => Bound::Lower,
+            3 => Bound::Exact,
+            _ => return None,
+        };
+
+        Some(TtEntry {
+            mv:    Move::from_u16(self.mv),
+            value: Value::new(self.value.into()),
+            eval:  Value::new(self.eval.into()),
+            depth: self.depth - 1,
+            bound,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY: u64 = 0x0123_4567_89ab_cdef;
+
+    #[test]
+    fn cluster_size() {
+        assert_eq!(64, size_of::<Cluster>());
+    }
+
+    #[test]
+    fn store_and_probe() {
+        let tt  = TranspositionTable::new(1);
+        let key = Key::from(KEY);
+        let mv  = Move::new(Square::E2, Square::E4);
+
+        assert_eq!(None, tt.probe(key));
+
+        tt.store(key, Some(mv), Value::PAWN, -Value::PAWN, 7, Bound::Lower);
+
+        assert_eq!(Some(TtEntry {
+            mv:    Some(mv),
+            value: Value::PAWN,
+            eval:  -Value::PAWN,
+            depth: 7,
+            bound: Bound::Lower,
+        }), tt.probe(key));
+
+        // a different position isn't found, even in the same cluster
+        assert_eq!(None, tt.probe(Key::from(KEY ^ 1)));
+    }
+
+    #[test]
+    fn store_keeps_move() {
+        let tt  = TranspositionTable::new(1);
+        let key = Key::from(KEY);
+        let mv  = Move::new(Square::G1, Square::F3);
+
+        tt.store(key, Some(mv), Value::ZERO, Value::ZERO, 3, Bound::Lower);
+        tt.store(key, None,     Value::ROOK, Value::ZERO, 4, Bound::Upper);
+
+        let entry = tt.probe(key).unwrap();
+
+        assert_eq!(Some(mv),     entry.mv);
+        assert_eq!(Value::ROOK,  entry.value);
+        assert_eq!(Bound::Upper, entry.bound);
+    }
+
+    #[test]
+    fn store_keeps_deeper() {
+        let tt  = TranspositionTable::new(1);
+        let key = Key::from(KEY);
+
+        tt.store(key, None, Value::ROOK, Value::ZERO, 20, Bound::Lower);
+        tt.store(key, None, Value::PAWN, Value::ZERO, 2,  Bound::Lower);
+
+        assert_eq!(20, tt.probe(key).unwrap().depth);
+
+        // but an exact score always replaces it
+        tt.store(key, None, Value::PAWN, Value::ZERO, 2, Bound::Exact);
+
+        assert_eq!(2, tt.probe(key).unwrap().depth);
+    }
+
+    #[test]
+    fn store_keeps_deeper_with_new_move() {
+        let tt  = TranspositionTable::new(1);
+        let key = Key::from(KEY);
+        let e4  = Move::new(Square::E2, Square::E4);
+        let d4  = Move::new(Square::D2, Square::D4);
+
+        tt.store(key, Some(e4), Value::ROOK, Value::ZERO, 20, Bound::Lower);
+        tt.store(key, Some(d4), Value::PAWN, Value::ZERO, 2,  Bound::Lower);
+
+        // the deeper result is kept, but the newer move replaces the old one
+        let entry = tt.probe(key).unwrap();
+
+        assert_eq!(20,          entry.depth);
+        assert_eq!(Value::ROOK, entry.value);
+        assert_eq!(Some(d4),    entry.mv);
+    }
+
+    #[test]
+    fn resize() {
+        let mut tt = TranspositionTable::new(0);
+
+        tt.store(Key::from(KEY), None, Value::ZERO, Value::ZERO, 1, Bound::Exact);
+        tt.resize(2);
+
+        assert_eq!(2 * 1024 * 1024, tt.clusters.len() * size_of::<Cluster>());
+        assert_eq!(None, tt.probe(Key::from(KEY)));
+    }
+
+    #[test]
+    fn replacement() {
+        // with a single cluster, every key competes for the same entries
+        let tt   = TranspositionTable::new(0);
+        let keys = (1..=CLUSTER_SIZE as u64).map(|i| Key::from((i << 32) | i)).collect::<Vec<_>>();
+
+        for (depth, &key) in (10..).zip(&keys) {
+            tt.store(key, None, Value::ZERO, Value::ZERO, depth, Bound::Exact);
+        }
+
+        // the shallowest entry makes way for a new one
+        tt.store(Key::from(0xffff), None, Value::ZERO, Value::ZERO, 1, Bound::Exact);
+
+        assert_eq!(None, tt.probe(keys[0]));
+        assert!(tt.probe(Key::from(0xffff)).is_some());
+        assert!(keys[1..].iter().all(|&key| tt.probe(key).is_some()));
+
+        // but entries from earlier searches age until they're worth less
+        // than even a shallow new one
+        tt.new_search();
+        tt.new_search();
+
+        assert!(tt.probe(Key::from(0xffff)).is_some());
+
+        tt.store(Key::from(0xfffe), None, Value::ZERO, Value::ZERO, 1, Bound::Exact);
+
+        assert_eq!(None, tt.probe(keys[1]));
+        assert!(tt.probe(Key::from(0xffff)).is_some());
+        assert!(tt.probe(Key::from(0xfffe)).is_some());
+    }
+
+    #[test]
+    fn probe_refreshes_generation() {
+        let tt  = TranspositionTable::new(0);
+        let key = Key::from(KEY);
+
+        tt.store(key, None, Value::ZERO, Value::ZERO, 1, Bound::Exact);
+        tt.new_search();
+
+        // the entry is stale until it's found again
+        assert_eq!(0, tt.hashfull());
+        assert!(tt.probe(key).is_some());
+        assert_eq!(166, tt.hashfull());
+    }
+
+    #[test]
+    fn hashfull() {
+        let mut tt = TranspositionTable::new(0);
+
+        assert_eq!(0, tt.hashfull());
+
+        for i in 1..=3 {
+            tt.store(Key::from((i << 32) | i), None, Value::ZERO, Value::ZERO, 1, Bound::Exact);
+        }
+
+        assert_eq!(500, tt.hashfull());
+
+        tt.clear();
+
+        assert_eq!(0, tt.hashfull());
+        assert_eq!(None, tt.probe(Key::from((1 << 32) | 1)));
+        assert_eq!(1,    tt.clusters.len());
+    }
+
+    #[test]
+    fn shared_between_threads() {
+        let tt = TranspositionTable::new(1);
+
+        std::thread::scope(|scope| {
+            for thread in 0..4_u64 {
+                let table = &tt;
+
+                let _ = scope.spawn(move || {
+                    for i in 0..1000_u64 {
+                        let key = Key::from((thread << 48) | (i << 20) | i);
+
+                        table.store(key, None, Value::new(i.try_into().unwrap()), Value::ZERO, 1, Bound::Exact);
+                        table.prefetch(key);
+                    }
+                });
+            }
+        });
+
+        assert!(tt.hashfull() > 0);
+    }
+}
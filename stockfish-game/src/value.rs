@@ -0,0 +1,225 @@
+use stockfish_core::prelude::*;
+
+use core::ops::{Add, AddAssign, Neg, Sub, SubAssign};
+
+/// The deepest the search may ever reach, counted in plies from the root.
+pub const MAX_PLY: u8 = 246;
+
+/// A score assigned to a position, piece, or exchange, measured in internal
+/// units where a pawn in the middlegame is worth [`Value::PAWN`].
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[must_use]
+pub struct Value(i32);
+
+impl Value {
+    // disable requiring documentation for self-documenting constants
+    #![allow(missing_docs)]
+
+    pub const ZERO: Self = Self(0);
+    pub const DRAW: Self = Self(0);
+
+    pub const MATE:            Self = Self(32_000);
+    pub const INFINITE:        Self = Self(32_001);
+    pub const MATE_IN_MAX_PLY: Self = Self(Self::MATE.0 - MAX_PLY as i32);
+
+    pub const PAWN:   Self = Self(126);
+    pub const KNIGHT: Self = Self(781);
+    pub const BISHOP: Self = Self(825);
+    pub const ROOK:   Self = Self(1276);
+    pub const QUEEN:  Self = Self(2538);
+}
+
+#[allow(clippy::multiple_inherent_impl)]
+impl Value {
+    /// The material value of each type of token, in the middlegame. Kings are
+    /// given no value, since they can never be traded off.
+    const TOKENS: [Self; Token::COUNT] = [
+        Self::PAWN, Self::KNIGHT, Self::BISHOP, Self::ROOK, Self::QUEEN, Self::ZERO,
+    ];
+
+    /// Creates a [`Value`] from a raw number of internal units.
+    #[inline]
+    pub const fn new(value: i32) -> Self {
+        Self(value)
+    }
+
+    /// Returns the middlegame material value of the given `token`.
+    #[inline]
+    pub fn of(token: Token) -> Self {
+        Self::TOKENS[token]
+    }
+
+    /// Returns the score for the side to move delivering mate `ply` plies
+    /// after the root of the search.
+    ///
+    /// Mates are scored relative to the root rather than to the position
+    /// they occur in, so that a shorter mate always scores better than a
+    /// longer one.
+    #[inline]
+    pub const fn mate_in(ply: u8) -> Self {
+        Self(Self::MATE.0 - ply as i32)
+    }
+
+    /// Returns the score for the side to move being mated `ply` plies after
+    /// the root of the search.
+    #[inline]
+    pub const fn mated_in(ply: u8) -> Self {
+        Self(-Self::MATE.0 + ply as i32)
+    }
+
+    /// Adjusts a score found `ply` plies from the root to be stored in a
+    /// transposition table, where mates are instead counted from the position
+    /// the score belongs to. The same position may be reached at any ply in
+    /// a later search.
+    #[inline]
+    pub const fn to_tt(self, ply: u8) -> Self {
+        match self.0 {
+            v if v >=  Self::MATE_IN_MAX_PLY.0 => Self(v + ply as i32),
+            v if v <= -Self::MATE_IN_MAX_PLY.0 => Self(v - ply as i32),
+            v                                  => Self(v),
+        }
+    }
+
+    /// Reverses [`Value::to_tt`] for a score retrieved from a transposition
+    /// table `ply` plies from the root.
+    #[inline]
+    pub const fn from_tt(self, ply: u8) -> Self {
+        match self.0 {
+            v if v >=  Self::MATE_IN_MAX_PLY.0 => Self(v - ply as i32),
+            v if v <= -Self::MATE_IN_MAX_PLY.0 => Self(v + ply as i32),
+            v                                  => Self(v),
+        }
+    }
+
+    /// Returns [`true`] if the score is for delivering or being mated,
+    /// rather than an evaluation of the position.
+    #[inline]
+    #[must_use]
+    pub const fn is_mate(self) -> bool {
+        self.0.abs() >= Self::MATE_IN_MAX_PLY.0
+    }
+
+    /// Returns the number of moves (not plies) until mate for a mate score,
+    /// as reported by UCI: positive if the side to move delivers mate, and
+    /// negative if it's the one being mated.
+    #[inline]
+    #[must_use]
+    // plies are deliberately rounded to whole moves, as UCI expects
+    #[allow(clippy::integer_division)]
+    pub const fn mate_moves(self) -> Option<i32> {
+        if !self.is_mate() {
+            return None;
+        }
+
+        Some(if self.0 > 0 {
+            (Self::MATE.0 - self.0 + 1) / 2
+        } else {
+            (-Self::MATE.0 - self.0) / 2
+        })
+    }
+}
+
+impl From<Value> for i32 {
+    #[inline]
+    fn from(value: Value) -> Self {
+        value.0
+    }
+}
+
+impl Add for Value {
+    type Output = Self;
+
+    #[inline]
+    fn add(self, rhs: Self) -> Self::Output {
+        Self(self.0 + rhs.0)
+    }
+}
+
+impl AddAssign for Value {
+    #[inline]
+    fn add_assign(&mut self, rhs: Self) {
+        self.0 += rhs.0;
+    }
+}
+
+impl Sub for Value {
+    type Output = Self;
+
+    #[inline]
+    fn sub(self, rhs: Self) -> Self::Output {
+        Self(self.0 - rhs.0)
+    }
+}
+
+impl SubAssign for Value {
+    #[inline]
+    fn sub_assign(&mut self, rhs: Self) {
+        self.0 -= rhs.0;
+    }
+}
+
+impl Neg for Value {
+    type Output = Self;
+
+    #[inline]
+    fn neg(self) -> Self::Output {
+        Self(-self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn of() {
+        assert_eq!(Value::PAWN,  Value::of(Token::Pawn));
+        assert_eq!(Value::QUEEN, Value::of(Token::Queen));
+        assert_eq!(Value::ZERO,  Value::of(Token::King));
+    }
+
+    #[test]
+    fn ops() {
+        let mut value = Value::KNIGHT - Value::PAWN;
+
+        value += Value::PAWN;
+        assert_eq!(Value::KNIGHT, value);
+
+        value -= Value::KNIGHT;
+        assert_eq!(Value::ZERO, value);
+
+        assert_eq!(Value::new(-126), -Value::PAWN);
+        assert_eq!(907, i32::from(Value::KNIGHT + Value::PAWN));
+    }
+
+    #[test]
+    fn mate() {
+        assert!(Value::mate_in(1) > Value::mate_in(3));
+        assert!(Value::mated_in(2) < Value::mated_in(4));
+        assert_eq!(-Value::mate_in(5), Value::mated_in(5));
+
+        assert!( Value::mate_in(MAX_PLY).is_mate());
+        assert!( Value::mated_in(MAX_PLY).is_mate());
+        assert!(!Value::QUEEN.is_mate());
+
+        // a mate delivered on the first ply is a mate in one, and being
+        // mated on the second ply means losing after one move
+        assert_eq!(Some(1),  Value::mate_in(1).mate_moves());
+        assert_eq!(Some(2),  Value::mate_in(3).mate_moves());
+        assert_eq!(Some(-1), Value::mated_in(2).mate_moves());
+        assert_eq!(Some(0),  Value::mated_in(0).mate_moves());
+        assert_eq!(None,     Value::ROOK.mate_moves());
+    }
+
+    #[test]
+    fn tt() {
+        // a mate fifteen plies from the root, found ten plies in, is a mate
+        // in five plies from the position itself
+        assert_eq!(Value::mate_in(5),   Value::mate_in(15).to_tt(10));
+        assert_eq!(Value::mated_in(5),  Value::mated_in(15).to_tt(10));
+        assert_eq!(Value::mate_in(15),  Value::mate_in(5).from_tt(10));
+
+        assert_eq!(Value::mated_in(7), Value::mated_in(7).to_tt(3).from_tt(3));
+        assert_eq!(Value::QUEEN,       Value::QUEEN.to_tt(3));
+    }
+}